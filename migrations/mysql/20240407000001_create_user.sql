@@ -0,0 +1,28 @@
+CREATE TABLE IF NOT EXISTS `user`
+(
+    `id`         BIGINT       NOT NULL AUTO_INCREMENT,
+    `created_at` DATETIME(3)  NOT NULL,
+    `updated_at` DATETIME(3)  NOT NULL,
+    `username`   VARCHAR(64)  NOT NULL,
+    `email`      VARCHAR(255) NOT NULL,
+    `password`   VARCHAR(255) NOT NULL,
+    `image`      VARCHAR(1024)         DEFAULT NULL,
+    `bio`        TEXT                  DEFAULT NULL,
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `uk_username` (`username`),
+    UNIQUE KEY `uk_email` (`email`)
+) ENGINE = InnoDB
+  DEFAULT CHARSET = utf8mb4;
+
+CREATE TABLE IF NOT EXISTS `user_follow`
+(
+    `id`               BIGINT      NOT NULL AUTO_INCREMENT,
+    `created_at`       DATETIME(3) NOT NULL,
+    `updated_at`       DATETIME(3) NOT NULL,
+    `follower_user_id` BIGINT      NOT NULL,
+    `followee_user_id` BIGINT      NOT NULL,
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `uk_follow` (`follower_user_id`, `followee_user_id`),
+    KEY `idx_followee_user_id` (`followee_user_id`)
+) ENGINE = InnoDB
+  DEFAULT CHARSET = utf8mb4;
@@ -0,0 +1,29 @@
+CREATE TABLE IF NOT EXISTS `article`
+(
+    `id`          BIGINT        NOT NULL AUTO_INCREMENT,
+    `created_at`  DATETIME(3)   NOT NULL,
+    `updated_at`  DATETIME(3)   NOT NULL,
+    `title`       VARCHAR(255)  NOT NULL,
+    `slug`        VARCHAR(255)  NOT NULL,
+    `description` VARCHAR(1024) NOT NULL,
+    `body`        MEDIUMTEXT    NOT NULL,
+    `tag_list`    TEXT          NOT NULL,
+    `user_id`     BIGINT        NOT NULL,
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `uk_slug` (`slug`),
+    KEY `idx_user_id` (`user_id`)
+) ENGINE = InnoDB
+  DEFAULT CHARSET = utf8mb4;
+
+CREATE TABLE IF NOT EXISTS `article_favorite`
+(
+    `id`         BIGINT      NOT NULL AUTO_INCREMENT,
+    `created_at` DATETIME(3) NOT NULL,
+    `updated_at` DATETIME(3) NOT NULL,
+    `user_id`    BIGINT      NOT NULL,
+    `article_id` BIGINT      NOT NULL,
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `uk_favorite` (`user_id`, `article_id`),
+    KEY `idx_article_id` (`article_id`)
+) ENGINE = InnoDB
+  DEFAULT CHARSET = utf8mb4;
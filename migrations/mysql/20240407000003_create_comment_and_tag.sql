@@ -0,0 +1,26 @@
+CREATE TABLE IF NOT EXISTS `comment`
+(
+    `id`         BIGINT      NOT NULL AUTO_INCREMENT,
+    `created_at` DATETIME(3) NOT NULL,
+    `updated_at` DATETIME(3) NOT NULL,
+    `body`       TEXT        NOT NULL,
+    `article_id` BIGINT      NOT NULL,
+    `user_id`    BIGINT      NOT NULL,
+    PRIMARY KEY (`id`),
+    KEY `idx_article_id` (`article_id`)
+) ENGINE = InnoDB
+  DEFAULT CHARSET = utf8mb4;
+
+CREATE TABLE IF NOT EXISTS `tag`
+(
+    `id`         BIGINT       NOT NULL AUTO_INCREMENT,
+    `created_at` DATETIME(3)  NOT NULL,
+    `updated_at` DATETIME(3)  NOT NULL,
+    `name`       VARCHAR(128) NOT NULL,
+    `article_id` BIGINT       NOT NULL,
+    `user_id`    BIGINT       NOT NULL,
+    PRIMARY KEY (`id`),
+    KEY `idx_name` (`name`),
+    KEY `idx_article_id` (`article_id`)
+) ENGINE = InnoDB
+  DEFAULT CHARSET = utf8mb4;
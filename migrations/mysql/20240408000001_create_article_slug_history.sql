@@ -0,0 +1,12 @@
+-- 文章改标题后旧 slug 记录在这里，旧链接据此重定向到当前 slug
+CREATE TABLE IF NOT EXISTS `article_slug_history`
+(
+    `id`         BIGINT       NOT NULL AUTO_INCREMENT,
+    `created_at` DATETIME(3)  NOT NULL,
+    `article_id` BIGINT       NOT NULL,
+    `slug`       VARCHAR(255) NOT NULL,
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `uk_slug` (`slug`),
+    KEY `idx_article_id` (`article_id`)
+) ENGINE = InnoDB
+  DEFAULT CHARSET = utf8mb4;
@@ -0,0 +1,44 @@
+-- 标签拆成 tags 字典表和 article_tags 关联表，只迁移仍存在的文章的标签
+CREATE TABLE IF NOT EXISTS `tags`
+(
+    `id`         BIGINT       NOT NULL AUTO_INCREMENT,
+    `created_at` DATETIME(3)  NOT NULL,
+    `name`       VARCHAR(128) NOT NULL,
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `uk_name` (`name`)
+) ENGINE = InnoDB
+  DEFAULT CHARSET = utf8mb4;
+
+CREATE TABLE IF NOT EXISTS `article_tags`
+(
+    `article_id` BIGINT NOT NULL,
+    `tag_id`     BIGINT NOT NULL,
+    PRIMARY KEY (`article_id`, `tag_id`),
+    KEY `idx_tag_id` (`tag_id`)
+) ENGINE = InnoDB
+  DEFAULT CHARSET = utf8mb4;
+
+-- 标签存成去掉首尾空白的小写形式。LOWER 也会转换非 ASCII 字母，
+-- 不过 _ci 排序规则下只差大小写的名字本就相等，查询时照样匹配
+UPDATE `tag`
+SET `name` = LOWER(TRIM(`name`));
+
+INSERT IGNORE INTO `tags` (`created_at`, `name`)
+SELECT MIN(t.`created_at`), t.`name`
+FROM `tag` t
+         JOIN `article` a ON a.`id` = t.`article_id`
+GROUP BY t.`name`;
+
+INSERT IGNORE INTO `article_tags` (`article_id`, `tag_id`)
+SELECT t.`article_id`, n.`id`
+FROM `tag` t
+         JOIN `article` a ON a.`id` = t.`article_id`
+         JOIN `tags` n ON n.`name` = t.`name`;
+
+UPDATE `article` a
+SET a.`tag_list` = COALESCE((SELECT JSON_ARRAYAGG(t.`name`)
+                             FROM `article_tags` tg
+                                      JOIN `tags` t ON t.`id` = tg.`tag_id`
+                             WHERE tg.`article_id` = a.`id`), '[]');
+
+DROP TABLE `tag`;
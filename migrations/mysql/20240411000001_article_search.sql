@@ -0,0 +1,3 @@
+-- 文章全文搜索，按 title/description/body 建 FULLTEXT 索引
+ALTER TABLE `article`
+    ADD FULLTEXT INDEX `ft_article_search` (`title`, `description`, `body`);
@@ -0,0 +1,17 @@
+-- 登录会话：每次登录/注册一条，保存刷新令牌的 SHA-256 和设备信息
+CREATE TABLE IF NOT EXISTS `user_session`
+(
+    `id`                 BIGINT       NOT NULL AUTO_INCREMENT,
+    `created_at`         DATETIME(3)  NOT NULL,
+    `updated_at`         DATETIME(3)  NOT NULL,
+    `user_id`            BIGINT       NOT NULL,
+    `refresh_token_hash` CHAR(64)     NOT NULL,
+    `user_agent`         VARCHAR(512)          DEFAULT NULL,
+    `ip`                 VARCHAR(64)           DEFAULT NULL,
+    `expires_at`         DATETIME(3)  NOT NULL,
+    `revoked_at`         DATETIME(3)           DEFAULT NULL,
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `uk_refresh_token_hash` (`refresh_token_hash`),
+    KEY `idx_user_id` (`user_id`)
+) ENGINE = InnoDB
+  DEFAULT CHARSET = utf8mb4;
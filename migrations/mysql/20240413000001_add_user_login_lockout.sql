@@ -0,0 +1,4 @@
+-- 登录失败计数和锁定截止时间，连续失败达到阈值后锁定账号
+ALTER TABLE `user`
+    ADD COLUMN `failed_login_count` INT         NOT NULL DEFAULT 0,
+    ADD COLUMN `locked_until`       DATETIME(3)          DEFAULT NULL;
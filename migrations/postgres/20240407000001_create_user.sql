@@ -0,0 +1,26 @@
+-- `user` is reserved in Postgres
+CREATE TABLE IF NOT EXISTS users
+(
+    id         BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    created_at TIMESTAMP(3)  NOT NULL,
+    updated_at TIMESTAMP(3)  NOT NULL,
+    username   VARCHAR(64)   NOT NULL,
+    email      VARCHAR(255)  NOT NULL,
+    password   VARCHAR(255)  NOT NULL,
+    image      VARCHAR(1024)          DEFAULT NULL,
+    bio        TEXT                   DEFAULT NULL,
+    CONSTRAINT uk_username UNIQUE (username),
+    CONSTRAINT uk_email UNIQUE (email)
+);
+
+CREATE TABLE IF NOT EXISTS user_follow
+(
+    id               BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    created_at       TIMESTAMP(3) NOT NULL,
+    updated_at       TIMESTAMP(3) NOT NULL,
+    follower_user_id BIGINT       NOT NULL,
+    followee_user_id BIGINT       NOT NULL,
+    CONSTRAINT uk_follow UNIQUE (follower_user_id, followee_user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_follow_followee_user_id ON user_follow (followee_user_id);
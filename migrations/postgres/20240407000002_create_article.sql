@@ -0,0 +1,27 @@
+CREATE TABLE IF NOT EXISTS article
+(
+    id          BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    created_at  TIMESTAMP(3)  NOT NULL,
+    updated_at  TIMESTAMP(3)  NOT NULL,
+    title       VARCHAR(255)  NOT NULL,
+    slug        VARCHAR(255)  NOT NULL,
+    description VARCHAR(1024) NOT NULL,
+    body        TEXT          NOT NULL,
+    tag_list    TEXT          NOT NULL,
+    user_id     BIGINT        NOT NULL,
+    CONSTRAINT uk_slug UNIQUE (slug)
+);
+
+CREATE INDEX IF NOT EXISTS idx_article_user_id ON article (user_id);
+
+CREATE TABLE IF NOT EXISTS article_favorite
+(
+    id         BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    created_at TIMESTAMP(3) NOT NULL,
+    updated_at TIMESTAMP(3) NOT NULL,
+    user_id    BIGINT       NOT NULL,
+    article_id BIGINT       NOT NULL,
+    CONSTRAINT uk_favorite UNIQUE (user_id, article_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_article_favorite_article_id ON article_favorite (article_id);
@@ -0,0 +1,24 @@
+CREATE TABLE IF NOT EXISTS comment
+(
+    id         BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    created_at TIMESTAMP(3) NOT NULL,
+    updated_at TIMESTAMP(3) NOT NULL,
+    body       TEXT         NOT NULL,
+    article_id BIGINT       NOT NULL,
+    user_id    BIGINT       NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_comment_article_id ON comment (article_id);
+
+CREATE TABLE IF NOT EXISTS tag
+(
+    id         BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    created_at TIMESTAMP(3) NOT NULL,
+    updated_at TIMESTAMP(3) NOT NULL,
+    name       VARCHAR(128) NOT NULL,
+    article_id BIGINT       NOT NULL,
+    user_id    BIGINT       NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_tag_name ON tag (name);
+CREATE INDEX IF NOT EXISTS idx_tag_article_id ON tag (article_id);
@@ -0,0 +1,11 @@
+-- 文章改标题后旧 slug 记录在这里，旧链接据此重定向到当前 slug
+CREATE TABLE IF NOT EXISTS article_slug_history
+(
+    id         BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    created_at TIMESTAMP(3) NOT NULL,
+    article_id BIGINT       NOT NULL,
+    slug       VARCHAR(255) NOT NULL,
+    CONSTRAINT uk_history_slug UNIQUE (slug)
+);
+
+CREATE INDEX IF NOT EXISTS idx_article_slug_history_article_id ON article_slug_history (article_id);
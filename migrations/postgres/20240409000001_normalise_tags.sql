@@ -0,0 +1,44 @@
+-- 标签拆成 tags 字典表和 article_tags 关联表，只迁移仍存在的文章的标签
+CREATE TABLE IF NOT EXISTS tags
+(
+    id         BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    created_at TIMESTAMP(3) NOT NULL,
+    name       VARCHAR(128) NOT NULL,
+    CONSTRAINT uk_name UNIQUE (name)
+);
+
+CREATE TABLE IF NOT EXISTS article_tags
+(
+    article_id BIGINT NOT NULL,
+    tag_id     BIGINT NOT NULL,
+    PRIMARY KEY (article_id, tag_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_article_tags_tag_id ON article_tags (tag_id);
+
+-- 标签存成去掉首尾空白、ASCII 字母小写的形式，与 normalize_tag 一致；
+-- LOWER 随 locale 也会转换非 ASCII 字母，所以用 TRANSLATE
+UPDATE tag
+SET name = TRANSLATE(TRIM(name), 'ABCDEFGHIJKLMNOPQRSTUVWXYZ', 'abcdefghijklmnopqrstuvwxyz');
+
+INSERT INTO tags (created_at, name)
+SELECT MIN(t.created_at), t.name
+FROM tag t
+         JOIN article a ON a.id = t.article_id
+GROUP BY t.name
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO article_tags (article_id, tag_id)
+SELECT DISTINCT t.article_id, n.id
+FROM tag t
+         JOIN article a ON a.id = t.article_id
+         JOIN tags n ON n.name = t.name
+ON CONFLICT DO NOTHING;
+
+UPDATE article a
+SET tag_list = COALESCE((SELECT json_agg(t.name ORDER BY t.name)::text
+                         FROM article_tags tg
+                                  JOIN tags t ON t.id = tg.tag_id
+                         WHERE tg.article_id = a.id), '[]');
+
+DROP TABLE tag;
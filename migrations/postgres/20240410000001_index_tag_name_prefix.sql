@@ -0,0 +1,2 @@
+-- 标签自动补全按 lower(name) 前缀匹配，text_pattern_ops 让 like 'ru%' 能走索引
+CREATE INDEX IF NOT EXISTS idx_tags_lower_name ON tags (lower(name) text_pattern_ops);
@@ -0,0 +1,10 @@
+-- 文章全文搜索：生成列保存加权的 tsvector，GIN 索引支持 @@ 查询
+-- 用 simple 配置只做小写化，不做词干提取，和其它后端的前缀匹配保持一致
+ALTER TABLE article
+    ADD COLUMN IF NOT EXISTS search_vector tsvector GENERATED ALWAYS AS (
+        setweight(to_tsvector('simple', title), 'A') ||
+        setweight(to_tsvector('simple', description), 'B') ||
+        setweight(to_tsvector('simple', body), 'D')
+        ) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_article_search_vector ON article USING GIN (search_vector);
@@ -0,0 +1,16 @@
+-- 登录会话：每次登录/注册一条，保存刷新令牌的 SHA-256 和设备信息
+CREATE TABLE IF NOT EXISTS user_session
+(
+    id                 BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    created_at         TIMESTAMP(3) NOT NULL,
+    updated_at         TIMESTAMP(3) NOT NULL,
+    user_id            BIGINT       NOT NULL,
+    refresh_token_hash CHAR(64)     NOT NULL,
+    user_agent         VARCHAR(512) NULL,
+    ip                 VARCHAR(64)  NULL,
+    expires_at         TIMESTAMP(3) NOT NULL,
+    revoked_at         TIMESTAMP(3) NULL,
+    CONSTRAINT uk_refresh_token_hash UNIQUE (refresh_token_hash)
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_session_user_id ON user_session (user_id);
@@ -0,0 +1,4 @@
+-- 登录失败计数和锁定截止时间，连续失败达到阈值后锁定账号
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS failed_login_count INTEGER      NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS locked_until       TIMESTAMP(3) NULL;
@@ -0,0 +1,25 @@
+CREATE TABLE IF NOT EXISTS "user"
+(
+    "id"         INTEGER  NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL,
+    "username"   TEXT     NOT NULL,
+    "email"      TEXT     NOT NULL,
+    "password"   TEXT     NOT NULL,
+    "image"      TEXT              DEFAULT NULL,
+    "bio"        TEXT              DEFAULT NULL,
+    CONSTRAINT "uk_username" UNIQUE ("username"),
+    CONSTRAINT "uk_email" UNIQUE ("email")
+);
+
+CREATE TABLE IF NOT EXISTS "user_follow"
+(
+    "id"               INTEGER  NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "created_at"       DATETIME NOT NULL,
+    "updated_at"       DATETIME NOT NULL,
+    "follower_user_id" INTEGER  NOT NULL,
+    "followee_user_id" INTEGER  NOT NULL,
+    CONSTRAINT "uk_follow" UNIQUE ("follower_user_id", "followee_user_id")
+);
+
+CREATE INDEX IF NOT EXISTS "idx_user_follow_followee_user_id" ON "user_follow" ("followee_user_id");
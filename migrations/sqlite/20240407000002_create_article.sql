@@ -0,0 +1,27 @@
+CREATE TABLE IF NOT EXISTS "article"
+(
+    "id"          INTEGER  NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "created_at"  DATETIME NOT NULL,
+    "updated_at"  DATETIME NOT NULL,
+    "title"       TEXT     NOT NULL,
+    "slug"        TEXT     NOT NULL,
+    "description" TEXT     NOT NULL,
+    "body"        TEXT     NOT NULL,
+    "tag_list"    TEXT     NOT NULL,
+    "user_id"     INTEGER  NOT NULL,
+    CONSTRAINT "uk_slug" UNIQUE ("slug")
+);
+
+CREATE INDEX IF NOT EXISTS "idx_article_user_id" ON "article" ("user_id");
+
+CREATE TABLE IF NOT EXISTS "article_favorite"
+(
+    "id"         INTEGER  NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL,
+    "user_id"    INTEGER  NOT NULL,
+    "article_id" INTEGER  NOT NULL,
+    CONSTRAINT "uk_favorite" UNIQUE ("user_id", "article_id")
+);
+
+CREATE INDEX IF NOT EXISTS "idx_article_favorite_article_id" ON "article_favorite" ("article_id");
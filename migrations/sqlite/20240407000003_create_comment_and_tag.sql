@@ -0,0 +1,24 @@
+CREATE TABLE IF NOT EXISTS "comment"
+(
+    "id"         INTEGER  NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL,
+    "body"       TEXT     NOT NULL,
+    "article_id" INTEGER  NOT NULL,
+    "user_id"    INTEGER  NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS "idx_comment_article_id" ON "comment" ("article_id");
+
+CREATE TABLE IF NOT EXISTS "tag"
+(
+    "id"         INTEGER  NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "created_at" DATETIME NOT NULL,
+    "updated_at" DATETIME NOT NULL,
+    "name"       TEXT     NOT NULL,
+    "article_id" INTEGER  NOT NULL,
+    "user_id"    INTEGER  NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS "idx_tag_name" ON "tag" ("name");
+CREATE INDEX IF NOT EXISTS "idx_tag_article_id" ON "tag" ("article_id");
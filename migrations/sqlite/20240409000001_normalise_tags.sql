@@ -0,0 +1,43 @@
+-- 标签拆成 tags 字典表和 article_tags 关联表，只迁移仍存在的文章的标签
+CREATE TABLE IF NOT EXISTS "tags"
+(
+    "id"         INTEGER  NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "created_at" DATETIME NOT NULL,
+    "name"       TEXT     NOT NULL,
+    CONSTRAINT "uk_name" UNIQUE ("name")
+);
+
+CREATE TABLE IF NOT EXISTS "article_tags"
+(
+    "article_id" INTEGER NOT NULL,
+    "tag_id"     INTEGER NOT NULL,
+    PRIMARY KEY ("article_id", "tag_id")
+);
+
+CREATE INDEX IF NOT EXISTS "idx_article_tags_tag_id" ON "article_tags" ("tag_id");
+
+-- 标签存成去掉首尾空白、ASCII 字母小写的形式，与 normalize_tag 一致
+UPDATE "tag"
+SET "name" = LOWER(TRIM("name"));
+
+INSERT OR IGNORE INTO "tags" ("created_at", "name")
+SELECT MIN(t."created_at"), t."name"
+FROM "tag" t
+         JOIN "article" a ON a."id" = t."article_id"
+GROUP BY t."name";
+
+INSERT OR IGNORE INTO "article_tags" ("article_id", "tag_id")
+SELECT t."article_id", n."id"
+FROM "tag" t
+         JOIN "article" a ON a."id" = t."article_id"
+         JOIN "tags" n ON n."name" = t."name";
+
+UPDATE "article"
+SET "tag_list" = (SELECT json_group_array("name")
+                  FROM (SELECT t."name"
+                        FROM "article_tags" tg
+                                 JOIN "tags" t ON t."id" = tg."tag_id"
+                        WHERE tg."article_id" = "article"."id"
+                        ORDER BY t."name"));
+
+DROP TABLE "tag";
@@ -0,0 +1,2 @@
+-- 标签自动补全用不区分大小写的 like 前缀匹配，需要 NOCASE 索引才能走索引
+CREATE INDEX IF NOT EXISTS "idx_tags_name_nocase" ON "tags" ("name" COLLATE NOCASE);
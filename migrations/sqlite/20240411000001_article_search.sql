@@ -0,0 +1,39 @@
+-- 文章全文搜索：FTS5 外部内容表，由触发器与 article 表保持同步
+CREATE VIRTUAL TABLE IF NOT EXISTS "article_fts" USING fts5
+(
+    "title",
+    "description",
+    "body",
+    content = 'article',
+    content_rowid = 'id',
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+CREATE TRIGGER IF NOT EXISTS "article_fts_insert"
+    AFTER INSERT
+    ON "article"
+BEGIN
+    INSERT INTO "article_fts" ("rowid", "title", "description", "body")
+    VALUES (new."id", new."title", new."description", new."body");
+END;
+
+CREATE TRIGGER IF NOT EXISTS "article_fts_delete"
+    AFTER DELETE
+    ON "article"
+BEGIN
+    INSERT INTO "article_fts" ("article_fts", "rowid", "title", "description", "body")
+    VALUES ('delete', old."id", old."title", old."description", old."body");
+END;
+
+CREATE TRIGGER IF NOT EXISTS "article_fts_update"
+    AFTER UPDATE OF "title", "description", "body"
+    ON "article"
+BEGIN
+    INSERT INTO "article_fts" ("article_fts", "rowid", "title", "description", "body")
+    VALUES ('delete', old."id", old."title", old."description", old."body");
+    INSERT INTO "article_fts" ("rowid", "title", "description", "body")
+    VALUES (new."id", new."title", new."description", new."body");
+END;
+
+INSERT INTO "article_fts" ("article_fts")
+VALUES ('rebuild');
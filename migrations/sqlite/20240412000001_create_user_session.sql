@@ -0,0 +1,16 @@
+-- 登录会话：每次登录/注册一条，保存刷新令牌的 SHA-256 和设备信息
+CREATE TABLE IF NOT EXISTS "user_session"
+(
+    "id"                 INTEGER  NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "created_at"         DATETIME NOT NULL,
+    "updated_at"         DATETIME NOT NULL,
+    "user_id"            INTEGER  NOT NULL,
+    "refresh_token_hash" TEXT     NOT NULL,
+    "user_agent"         TEXT     NULL,
+    "ip"                 TEXT     NULL,
+    "expires_at"         DATETIME NOT NULL,
+    "revoked_at"         DATETIME NULL,
+    CONSTRAINT "uk_refresh_token_hash" UNIQUE ("refresh_token_hash")
+);
+
+CREATE INDEX IF NOT EXISTS "idx_user_session_user_id" ON "user_session" ("user_id");
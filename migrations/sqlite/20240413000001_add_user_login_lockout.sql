@@ -0,0 +1,3 @@
+-- 登录失败计数和锁定截止时间，连续失败达到阈值后锁定账号
+ALTER TABLE "user" ADD COLUMN "failed_login_count" INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE "user" ADD COLUMN "locked_until" DATETIME NULL;
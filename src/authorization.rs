@@ -0,0 +1,33 @@
+use crate::config::PolicyConfig;
+use crate::error::AppError;
+use crate::models::article::ArticleEntity;
+use crate::models::comment::CommentEntity;
+
+/// Only the author may update or delete an article.
+pub fn ensure_article_author(article: &ArticleEntity, user_id: i64) -> Result<(), AppError> {
+    if article.user_id != user_id {
+        return Err(AppError::Forbidden("article".to_string()));
+    }
+    Ok(())
+}
+
+/// A comment may be deleted by its author, or by the article's author when
+/// the policy allows it. The comment must belong to `article`, otherwise it
+/// is reported as missing.
+pub fn ensure_can_delete_comment(
+    policy: &PolicyConfig,
+    article: &ArticleEntity,
+    comment: &CommentEntity,
+    user_id: i64,
+) -> Result<(), AppError> {
+    if comment.article_id != article.id {
+        return Err(AppError::NotFound("comment".to_string()));
+    }
+    if comment.user_id == user_id {
+        return Ok(());
+    }
+    if policy.article_author_can_delete_comments && article.user_id == user_id {
+        return Ok(());
+    }
+    Err(AppError::Forbidden("comment".to_string()))
+}
@@ -0,0 +1,634 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::time::Duration;
+
+use derive_more::Display;
+use jsonwebtoken::Algorithm;
+use serde::Deserialize;
+
+/// Upper bound for every configured lifetime, so that expiry times stay far
+/// from the overflow limits of the time types they are added to.
+pub const MAX_LIFETIME_SECS: u64 = 60 * 60 * 24 * 365 * 10;
+
+/// Shortest HS256 secret accepted: as long as the SHA-256 output, as RFC 7518
+/// requires.
+pub const MIN_SECRET_BYTES: usize = 32;
+
+/// Application configuration.
+///
+/// Values are resolved in order: built-in defaults, then the TOML file named by
+/// `CONFIG_FILE` (or `config.toml` if present), then environment variables
+/// (including those loaded from `.env`).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub jwt: JwtConfig,
+    pub cors: CorsConfig,
+    pub log: LogConfig,
+    pub policy: PolicyConfig,
+    pub login: LoginConfig,
+    pub rate_limit: RateLimitConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub bind_address: String,
+    /// Take the client IP from `Forwarded`/`X-Forwarded-For`. Only safe
+    /// behind a proxy that sets them, since clients can forge them.
+    pub trust_proxy: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub min_connections: u32,
+    pub max_connections: u32,
+    pub acquire_timeout_secs: u64,
+    pub idle_timeout_secs: u64,
+    pub auto_migrate: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct JwtConfig {
+    /// HS256 secret, used as the only key when `keys` is empty. At least
+    /// [`MIN_SECRET_BYTES`] long; keep it out of version control.
+    pub secret: String,
+    /// Lifetime of an access token.
+    pub expires_in_secs: u64,
+    /// Lifetime of a session: how long its refresh token stays usable.
+    /// Every refresh starts it again.
+    pub refresh_expires_in_secs: u64,
+    /// `id` of the key in `keys` that signs new tokens; the first key when
+    /// empty.
+    pub signing_key: String,
+    /// Every key that verifies tokens. Keeping the previous key listed after
+    /// switching `signing_key` lets its tokens live out their lifetime.
+    pub keys: Vec<JwtKeyConfig>,
+    /// Also hand out the access token in an HttpOnly cookie, and accept it
+    /// from there, for browser clients.
+    pub cookie: bool,
+    /// Mark that cookie `Secure` so browsers only send it over HTTPS.
+    pub cookie_secure: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JwtKeyConfig {
+    /// Sent as the token's `kid` header.
+    pub id: String,
+    /// `HS256`, `RS256` or `ES256`.
+    pub algorithm: Algorithm,
+    /// HS256 only.
+    pub secret: Option<String>,
+    /// PKCS#8 PEM file. RS256/ES256 only, and only needed to sign.
+    pub private_key_file: Option<String>,
+    /// PEM file with the public key. RS256/ES256 only.
+    pub public_key_file: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CorsConfig {
+    /// `*` allows any origin, without credentials.
+    pub allowed_origins: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    pub level: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PolicyConfig {
+    /// Whether an article's author may delete other users' comments on it.
+    pub article_author_can_delete_comments: bool,
+}
+
+/// Request throttling per client: per user when authenticated, else per IP.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    /// GET/HEAD requests allowed per minute, across all scopes.
+    pub reads_per_minute: u32,
+    /// Other requests allowed per minute in a scope not listed in `scopes`.
+    pub writes_per_minute: u32,
+    /// Writes allowed per minute by route scope: `users`, `user`,
+    /// `articles` (including comments and favorites), `profiles`, `tags`.
+    pub scopes: HashMap<String, u32>,
+}
+
+/// Brute-force protection for login and registration.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LoginConfig {
+    /// Login and registration attempts allowed per minute from one IP.
+    pub ip_attempts_per_minute: u32,
+    /// Login attempts allowed per minute for one account, or for one email
+    /// when it names no account.
+    pub account_attempts_per_minute: u32,
+    /// Failed logins in a row that lock the account.
+    pub lockout_threshold: u32,
+    /// Length of the first lockout. Every further `lockout_threshold`
+    /// failures doubles it, up to `max_lockout_secs`.
+    pub lockout_secs: u64,
+    pub max_lockout_secs: u64,
+}
+
+#[derive(Debug, Display)]
+pub enum ConfigError {
+    #[display(fmt = "could not read config file {}: {}", _0, _1)]
+    Io(String, std::io::Error),
+    #[display(fmt = "could not parse config file {}: {}", _0, _1)]
+    Parse(String, toml::de::Error),
+    #[display(fmt = "invalid value for {}: {}", _0, _1)]
+    Invalid(&'static str, String),
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            bind_address: "127.0.0.1:3000".to_string(),
+            trust_proxy: false,
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        let num_cores = num_cpus::get() as u32;
+        DatabaseConfig {
+            url: String::new(),
+            min_connections: (num_cores / 2).max(1),
+            max_connections: num_cores.max(2),
+            acquire_timeout_secs: 30,
+            idle_timeout_secs: 600,
+            auto_migrate: true,
+        }
+    }
+}
+
+impl Default for JwtConfig {
+    fn default() -> Self {
+        JwtConfig {
+            secret: String::new(),
+            expires_in_secs: 60 * 60 * 2,
+            refresh_expires_in_secs: 60 * 60 * 24 * 30,
+            signing_key: String::new(),
+            keys: Vec::new(),
+            cookie: false,
+            cookie_secure: true,
+        }
+    }
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        CorsConfig {
+            allowed_origins: vec!["*".to_string()],
+        }
+    }
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            level: "info".to_string(),
+        }
+    }
+}
+
+impl Default for LoginConfig {
+    fn default() -> Self {
+        LoginConfig {
+            ip_attempts_per_minute: 20,
+            account_attempts_per_minute: 5,
+            lockout_threshold: 5,
+            lockout_secs: 60,
+            max_lockout_secs: 60 * 60,
+        }
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        RateLimitConfig {
+            enabled: true,
+            reads_per_minute: 300,
+            writes_per_minute: 60,
+            scopes: HashMap::from([("articles".to_string(), 30), ("profiles".to_string(), 30)]),
+        }
+    }
+}
+
+impl Default for PolicyConfig {
+    fn default() -> Self {
+        PolicyConfig {
+            article_author_can_delete_comments: true,
+        }
+    }
+}
+
+impl Config {
+    /// Loads and validates the configuration. `.env` must already be loaded.
+    pub fn load() -> Result<Config, ConfigError> {
+        let mut config = match env::var("CONFIG_FILE") {
+            Ok(path) => Config::from_file(&path)?,
+            Err(_) if fs::metadata("config.toml").is_ok() => Config::from_file("config.toml")?,
+            Err(_) => Config::default(),
+        };
+        config.apply_env()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn from_file(path: &str) -> Result<Config, ConfigError> {
+        let content = fs::read_to_string(path).map_err(|e| ConfigError::Io(path.to_string(), e))?;
+        toml::from_str(&content).map_err(|e| ConfigError::Parse(path.to_string(), e))
+    }
+
+    fn apply_env(&mut self) -> Result<(), ConfigError> {
+        env_override("BIND_ADDRESS", &mut self.server.bind_address)?;
+        env_override("TRUST_PROXY", &mut self.server.trust_proxy)?;
+
+        env_override("DATABASE_URL", &mut self.database.url)?;
+        env_override("DATABASE_MIN_CONNECTIONS", &mut self.database.min_connections)?;
+        env_override("DATABASE_MAX_CONNECTIONS", &mut self.database.max_connections)?;
+        env_override(
+            "DATABASE_ACQUIRE_TIMEOUT_SECS",
+            &mut self.database.acquire_timeout_secs,
+        )?;
+        env_override("DATABASE_IDLE_TIMEOUT_SECS", &mut self.database.idle_timeout_secs)?;
+        env_override("DATABASE_AUTO_MIGRATE", &mut self.database.auto_migrate)?;
+
+        env_override("JWT_SECRET", &mut self.jwt.secret)?;
+        env_override("JWT_EXPIRES_IN_SECS", &mut self.jwt.expires_in_secs)?;
+        env_override(
+            "JWT_REFRESH_EXPIRES_IN_SECS",
+            &mut self.jwt.refresh_expires_in_secs,
+        )?;
+        env_override("JWT_SIGNING_KEY", &mut self.jwt.signing_key)?;
+        env_override("JWT_COOKIE", &mut self.jwt.cookie)?;
+        env_override("JWT_COOKIE_SECURE", &mut self.jwt.cookie_secure)?;
+
+        if let Ok(origins) = env::var("CORS_ALLOWED_ORIGINS") {
+            self.cors.allowed_origins = origins
+                .split(',')
+                .map(|o| o.trim().to_string())
+                .filter(|o| !o.is_empty())
+                .collect();
+        }
+
+        env_override("LOG_LEVEL", &mut self.log.level)?;
+
+        env_override(
+            "ARTICLE_AUTHOR_CAN_DELETE_COMMENTS",
+            &mut self.policy.article_author_can_delete_comments,
+        )?;
+
+        env_override(
+            "LOGIN_IP_ATTEMPTS_PER_MINUTE",
+            &mut self.login.ip_attempts_per_minute,
+        )?;
+        env_override(
+            "LOGIN_ACCOUNT_ATTEMPTS_PER_MINUTE",
+            &mut self.login.account_attempts_per_minute,
+        )?;
+        env_override("LOGIN_LOCKOUT_THRESHOLD", &mut self.login.lockout_threshold)?;
+        env_override("LOGIN_LOCKOUT_SECS", &mut self.login.lockout_secs)?;
+        env_override("LOGIN_MAX_LOCKOUT_SECS", &mut self.login.max_lockout_secs)?;
+
+        env_override("RATE_LIMIT_ENABLED", &mut self.rate_limit.enabled)?;
+        env_override(
+            "RATE_LIMIT_READS_PER_MINUTE",
+            &mut self.rate_limit.reads_per_minute,
+        )?;
+        env_override(
+            "RATE_LIMIT_WRITES_PER_MINUTE",
+            &mut self.rate_limit.writes_per_minute,
+        )?;
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.server.bind_address.parse::<SocketAddr>().is_err() {
+            return Err(ConfigError::Invalid(
+                "server.bind_address",
+                format!("{:?} is not a socket address", self.server.bind_address),
+            ));
+        }
+        if self.database.url.is_empty() {
+            return Err(ConfigError::Invalid("database.url", "must be set".to_string()));
+        }
+        if self.database.max_connections == 0
+            || self.database.min_connections > self.database.max_connections
+        {
+            return Err(ConfigError::Invalid(
+                "database.max_connections",
+                "must be positive and not less than database.min_connections".to_string(),
+            ));
+        }
+        self.jwt.validate()?;
+        if self.jwt.expires_in_secs == 0 {
+            return Err(ConfigError::Invalid(
+                "jwt.expires_in_secs",
+                "must be positive".to_string(),
+            ));
+        }
+        if self.jwt.refresh_expires_in_secs <= self.jwt.expires_in_secs {
+            return Err(ConfigError::Invalid(
+                "jwt.refresh_expires_in_secs",
+                "must be longer than jwt.expires_in_secs".to_string(),
+            ));
+        }
+        if self.jwt.refresh_expires_in_secs > MAX_LIFETIME_SECS {
+            return Err(ConfigError::Invalid(
+                "jwt.refresh_expires_in_secs",
+                format!("must not exceed {} seconds", MAX_LIFETIME_SECS),
+            ));
+        }
+        if self.cors.allowed_origins.is_empty() {
+            return Err(ConfigError::Invalid(
+                "cors.allowed_origins",
+                "must list at least one origin or \"*\"".to_string(),
+            ));
+        }
+        // 允许任意来源时，任何网站都能带着 Cookie 以用户身份发请求
+        if self.jwt.cookie && self.cors.allowed_origins.iter().any(|o| o == "*") {
+            return Err(ConfigError::Invalid(
+                "cors.allowed_origins",
+                "must list the allowed origins when jwt.cookie is on".to_string(),
+            ));
+        }
+        if self.login.ip_attempts_per_minute == 0 || self.login.account_attempts_per_minute == 0 {
+            return Err(ConfigError::Invalid(
+                "login.ip_attempts_per_minute",
+                "login attempt limits must be positive".to_string(),
+            ));
+        }
+        if self.login.lockout_threshold == 0 || self.login.lockout_secs == 0 {
+            return Err(ConfigError::Invalid(
+                "login.lockout_threshold",
+                "lockout_threshold and lockout_secs must be positive".to_string(),
+            ));
+        }
+        if self.login.max_lockout_secs < self.login.lockout_secs {
+            return Err(ConfigError::Invalid(
+                "login.max_lockout_secs",
+                "must not be less than login.lockout_secs".to_string(),
+            ));
+        }
+        if self.login.max_lockout_secs > MAX_LIFETIME_SECS {
+            return Err(ConfigError::Invalid(
+                "login.max_lockout_secs",
+                format!("must not exceed {} seconds", MAX_LIFETIME_SECS),
+            ));
+        }
+        if self.rate_limit.reads_per_minute == 0
+            || self.rate_limit.writes_per_minute == 0
+            || self.rate_limit.scopes.values().any(|&limit| limit == 0)
+        {
+            return Err(ConfigError::Invalid(
+                "rate_limit",
+                "limits must be positive; set rate_limit.enabled = false to disable".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl JwtConfig {
+    /// The key that signs new tokens, `None` when only `secret` is set.
+    pub fn signing_key_config(&self) -> Option<&JwtKeyConfig> {
+        if self.signing_key.is_empty() {
+            self.keys.first()
+        } else {
+            self.keys.iter().find(|key| key.id == self.signing_key)
+        }
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.keys.is_empty() {
+            if self.secret.is_empty() {
+                return Err(ConfigError::Invalid(
+                    "jwt.secret",
+                    "must be set when jwt.keys is empty".to_string(),
+                ));
+            }
+            if self.secret.len() < MIN_SECRET_BYTES {
+                return Err(ConfigError::Invalid(
+                    "jwt.secret",
+                    format!("must be at least {} bytes", MIN_SECRET_BYTES),
+                ));
+            }
+            return Ok(());
+        }
+        for (i, key) in self.keys.iter().enumerate() {
+            if key.id.is_empty() {
+                return Err(ConfigError::Invalid("jwt.keys", "id must be set".to_string()));
+            }
+            if self.keys[..i].iter().any(|other| other.id == key.id) {
+                return Err(ConfigError::Invalid(
+                    "jwt.keys",
+                    format!("duplicate id {:?}", key.id),
+                ));
+            }
+            let missing = match key.algorithm {
+                Algorithm::HS256 if key.secret.as_deref().unwrap_or_default().is_empty() => {
+                    Some("secret")
+                }
+                Algorithm::HS256 => None,
+                Algorithm::RS256 | Algorithm::ES256 if key.public_key_file.is_none() => {
+                    Some("public_key_file")
+                }
+                Algorithm::RS256 | Algorithm::ES256 => None,
+                algorithm => {
+                    return Err(ConfigError::Invalid(
+                        "jwt.keys",
+                        format!(
+                            "key {:?}: unsupported algorithm {:?}, use HS256, RS256 or ES256",
+                            key.id, algorithm
+                        ),
+                    ))
+                }
+            };
+            if let Some(field) = missing {
+                return Err(ConfigError::Invalid(
+                    "jwt.keys",
+                    format!("key {:?}: {} must be set", key.id, field),
+                ));
+            }
+            let secret = key.secret.as_deref().unwrap_or_default();
+            if key.algorithm == Algorithm::HS256 && secret.len() < MIN_SECRET_BYTES {
+                return Err(ConfigError::Invalid(
+                    "jwt.keys",
+                    format!(
+                        "key {:?}: secret must be at least {} bytes",
+                        key.id, MIN_SECRET_BYTES
+                    ),
+                ));
+            }
+        }
+        match self.signing_key_config() {
+            None => Err(ConfigError::Invalid(
+                "jwt.signing_key",
+                format!("no key in jwt.keys has id {:?}", self.signing_key),
+            )),
+            Some(key) if key.algorithm != Algorithm::HS256 && key.private_key_file.is_none() => {
+                Err(ConfigError::Invalid(
+                    "jwt.signing_key",
+                    format!("key {:?} has no private_key_file", key.id),
+                ))
+            }
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+impl LoginConfig {
+    /// How long to lock an account after its `failures`-th failed login in
+    /// a row: `lockout_secs` at the threshold, doubling at each further
+    /// multiple of it, `None` in between.
+    pub fn lockout(&self, failures: u32) -> Option<Duration> {
+        if failures == 0 || !failures.is_multiple_of(self.lockout_threshold) {
+            return None;
+        }
+        let doublings = (failures / self.lockout_threshold - 1).min(32);
+        let secs = self.lockout_secs.saturating_mul(1 << doublings);
+        Some(Duration::from_secs(secs.min(self.max_lockout_secs)))
+    }
+}
+
+impl DatabaseConfig {
+    pub fn acquire_timeout(&self) -> Duration {
+        Duration::from_secs(self.acquire_timeout_secs)
+    }
+
+    pub fn idle_timeout(&self) -> Duration {
+        Duration::from_secs(self.idle_timeout_secs)
+    }
+}
+
+fn env_override<T>(key: &'static str, target: &mut T) -> Result<(), ConfigError>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    if let Ok(value) = env::var(key) {
+        *target = value
+            .parse()
+            .map_err(|e: T::Err| ConfigError::Invalid(key, e.to_string()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn valid_config() -> Config {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        config.jwt.secret = "a-test-secret-of-at-least-32-bytes".to_string();
+        config
+    }
+
+    #[test]
+    fn defaults_with_a_database_and_secret_are_valid() {
+        assert!(valid_config().validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_missing_and_short_hs256_secrets() {
+        for secret in ["", "realworld", "change-me"] {
+            let mut config = valid_config();
+            config.jwt.secret = secret.to_string();
+            assert!(
+                matches!(
+                    config.validate(),
+                    Err(ConfigError::Invalid("jwt.secret", _))
+                ),
+                "{:?}",
+                secret
+            );
+        }
+
+        let mut config = valid_config();
+        config.jwt.keys = vec![JwtKeyConfig {
+            id: "2024".to_string(),
+            algorithm: Algorithm::HS256,
+            secret: Some("realworld".to_string()),
+            private_key_file: None,
+            public_key_file: None,
+        }];
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::Invalid("jwt.keys", _))
+        ));
+        config.jwt.keys[0].secret = Some("x".repeat(MIN_SECRET_BYTES));
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_lifetimes_beyond_the_bound() {
+        let mut config = valid_config();
+        config.jwt.refresh_expires_in_secs = MAX_LIFETIME_SECS + 1;
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::Invalid("jwt.refresh_expires_in_secs", _))
+        ));
+
+        // 超过 i64 的值也不会在处理请求时回绕成负数
+        config.jwt.expires_in_secs = u64::MAX - 1;
+        config.jwt.refresh_expires_in_secs = u64::MAX;
+        assert!(config.validate().is_err());
+
+        let mut config = valid_config();
+        config.login.max_lockout_secs = MAX_LIFETIME_SECS + 1;
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::Invalid("login.max_lockout_secs", _))
+        ));
+    }
+
+    fn login_config() -> LoginConfig {
+        LoginConfig {
+            lockout_threshold: 5,
+            lockout_secs: 60,
+            max_lockout_secs: 3600,
+            ..LoginConfig::default()
+        }
+    }
+
+    #[test]
+    fn lockout_starts_at_the_threshold_and_doubles_per_run() {
+        let login = login_config();
+        for failures in [0, 1, 4, 6, 9, 11] {
+            assert_eq!(login.lockout(failures), None, "{} failures", failures);
+        }
+        assert_eq!(login.lockout(5), Some(Duration::from_secs(60)));
+        assert_eq!(login.lockout(10), Some(Duration::from_secs(120)));
+        assert_eq!(login.lockout(15), Some(Duration::from_secs(240)));
+        assert_eq!(login.lockout(30), Some(Duration::from_secs(1920)));
+    }
+
+    #[test]
+    fn lockout_is_capped_at_max_lockout_secs() {
+        let login = login_config();
+        assert_eq!(login.lockout(35), Some(Duration::from_secs(3600)));
+        // 翻倍次数封顶，失败次数再大也不会溢出；u32::MAX 是 5 的倍数
+        assert_eq!(login.lockout(5 * 1000), Some(Duration::from_secs(3600)));
+        assert_eq!(login.lockout(u32::MAX), Some(Duration::from_secs(3600)));
+    }
+}
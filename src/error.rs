@@ -0,0 +1,268 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+use actix_web::http::{header, StatusCode};
+use actix_web::{HttpResponse, ResponseError};
+use serde::Serialize;
+
+/// Errors surfaced to API clients.
+///
+/// Every variant is rendered in the RealWorld shape
+/// `{"errors": {"<field>": ["<message>", ..]}}`.
+#[derive(Debug)]
+pub enum AppError {
+    /// The named resource (`article`, `comment`, ..) does not exist.
+    NotFound(String),
+    /// A unique field (`email`, `username`, ..) is already taken.
+    Conflict(String),
+    /// The caller may not act on the named resource.
+    Forbidden(String),
+    Unauthorized(String),
+    /// The request's access token is missing or was rejected.
+    Unauthenticated(TokenError),
+    /// Rate limited; the client may retry after the given time.
+    TooManyRequests(Duration),
+    /// Per-field validation messages.
+    Validation(BTreeMap<String, Vec<String>>),
+    /// Details are logged, never sent to the client.
+    Internal(String),
+    /// The resource now lives at the given location (301, for GET/HEAD).
+    MovedPermanently(String),
+    /// Same as `MovedPermanently` but the client must repeat the method and
+    /// body (308, for writes).
+    PermanentRedirect(String),
+}
+
+/// Why a request's access token was not accepted. Reported in the body and
+/// in the `WWW-Authenticate` challenge (RFC 6750).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenError {
+    /// Neither an `Authorization` header nor a token cookie was sent.
+    Missing,
+    /// The `Authorization` header could not be parsed.
+    Malformed(&'static str),
+    /// The token is expired, forged, signed with an unknown key or belongs
+    /// to a revoked session.
+    Invalid(&'static str),
+}
+
+/// Realm named in `WWW-Authenticate` challenges.
+const REALM: &str = "realworld";
+
+impl TokenError {
+    fn message(&self) -> &'static str {
+        match self {
+            TokenError::Missing => "is missing",
+            TokenError::Malformed(message) | TokenError::Invalid(message) => message,
+        }
+    }
+
+    fn challenge(&self) -> String {
+        let error = match self {
+            TokenError::Missing => return format!("Bearer realm=\"{}\"", REALM),
+            TokenError::Malformed(_) => "invalid_request",
+            TokenError::Invalid(_) => "invalid_token",
+        };
+        format!(
+            "Bearer realm=\"{}\", error=\"{}\", error_description=\"{}\"",
+            REALM,
+            error,
+            self.message()
+        )
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorsBody {
+    pub errors: BTreeMap<String, Vec<String>>,
+}
+
+impl AppError {
+    pub fn validation(field: &str, message: &str) -> Self {
+        let mut errors = BTreeMap::new();
+        errors.insert(field.to_string(), vec![message.to_string()]);
+        AppError::Validation(errors)
+    }
+
+    fn errors(&self) -> BTreeMap<String, Vec<String>> {
+        let (field, message) = match self {
+            AppError::NotFound(resource) => (resource.as_str(), "not found"),
+            AppError::Conflict(field) => (field.as_str(), "has already been taken"),
+            AppError::Forbidden(resource) => (resource.as_str(), "forbidden"),
+            AppError::Unauthorized(message) => ("body", message.as_str()),
+            AppError::Unauthenticated(e) => ("token", e.message()),
+            AppError::TooManyRequests(_) => ("body", "too many requests, try again later"),
+            AppError::Validation(errors) => return errors.clone(),
+            AppError::Internal(_) => ("body", "internal server error"),
+            AppError::MovedPermanently(_) | AppError::PermanentRedirect(_) => {
+                return BTreeMap::new()
+            }
+        };
+        let mut errors = BTreeMap::new();
+        errors.insert(field.to_string(), vec![message.to_string()]);
+        errors
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::NotFound(resource) => write!(f, "{} not found", resource),
+            AppError::Conflict(field) => write!(f, "{} has already been taken", field),
+            AppError::Forbidden(resource) => write!(f, "{} forbidden", resource),
+            AppError::Unauthorized(message) => write!(f, "unauthorized: {}", message),
+            AppError::Unauthenticated(e) => write!(f, "token {}", e.message()),
+            AppError::TooManyRequests(retry_after) => {
+                write!(f, "too many requests, retry after {:?}", retry_after)
+            }
+            AppError::Validation(errors) => write!(f, "validation failed: {:?}", errors),
+            AppError::Internal(message) => write!(f, "internal error: {}", message),
+            AppError::MovedPermanently(location) | AppError::PermanentRedirect(location) => {
+                write!(f, "moved to {}", location)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::Unauthorized(_) | AppError::Unauthenticated(_) => StatusCode::UNAUTHORIZED,
+            AppError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::MovedPermanently(_) => StatusCode::MOVED_PERMANENTLY,
+            AppError::PermanentRedirect(_) => StatusCode::PERMANENT_REDIRECT,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            AppError::Internal(message) => log::error!("internal error: {}", message),
+            AppError::MovedPermanently(location) | AppError::PermanentRedirect(location) => {
+                return HttpResponse::build(self.status_code())
+                    .insert_header((header::LOCATION, location.as_str()))
+                    .finish();
+            }
+            AppError::Unauthenticated(e) => {
+                return HttpResponse::build(self.status_code())
+                    .insert_header((header::WWW_AUTHENTICATE, e.challenge()))
+                    .json(ErrorsBody {
+                        errors: self.errors(),
+                    });
+            }
+            AppError::TooManyRequests(retry_after) => {
+                // Retry-After 只能是整秒，向上取整
+                let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+                return HttpResponse::build(self.status_code())
+                    .insert_header((header::RETRY_AFTER, secs.max(1)))
+                    .json(ErrorsBody {
+                        errors: self.errors(),
+                    });
+            }
+            _ => {}
+        }
+        HttpResponse::build(self.status_code()).json(ErrorsBody {
+            errors: self.errors(),
+        })
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(e: sqlx::Error) -> Self {
+        match e {
+            sqlx::Error::RowNotFound => AppError::NotFound("record".to_string()),
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                AppError::Conflict(unique_violation_field(db.message()))
+            }
+            e => AppError::Internal(e.to_string()),
+        }
+    }
+}
+
+/// Extracts the field from a duplicate-key message. Unique keys are named
+/// `uk_<field>` in the migrations.
+///
+/// MySQL reports the key (`Duplicate entry 'a@b.c' for key 'user.uk_email'`),
+/// Postgres the constraint (`.. violates unique constraint "uk_email"`),
+/// SQLite the columns (`UNIQUE constraint failed: user.email`); for composite
+/// keys such as `user_follow` the table suffix matches the key name.
+fn unique_violation_field(message: &str) -> String {
+    if let Some((_, constraint)) = message.split_once("violates unique constraint \"") {
+        return constraint
+            .trim_end_matches('"')
+            .trim_start_matches("uk_")
+            .to_string();
+    }
+    if let Some(columns) = message.strip_prefix("UNIQUE constraint failed: ") {
+        let columns: Vec<&str> = columns.split(", ").collect();
+        let field = match columns.as_slice() {
+            [column] => column.rsplit('.').next(),
+            [column, ..] => column
+                .split('.')
+                .next()
+                .and_then(|table| table.rsplit('_').next()),
+            [] => None,
+        };
+        return field
+            .filter(|field| !field.is_empty())
+            .unwrap_or("record")
+            .to_string();
+    }
+    message
+        .rsplit("for key '")
+        .next()
+        .map(|key| key.trim_end_matches('\''))
+        .and_then(|key| key.rsplit('.').next())
+        .map(|key| key.trim_start_matches("uk_").to_string())
+        .filter(|field| !field.is_empty() && !field.contains(' '))
+        .unwrap_or_else(|| "record".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::unique_violation_field;
+
+    #[test]
+    fn reads_the_field_from_mysql_keys() {
+        // MySQL 8 带表名，5.7 不带
+        let message = "Duplicate entry 'alice@example.com' for key 'user.uk_email'";
+        assert_eq!(unique_violation_field(message), "email");
+        let message = "Duplicate entry 'alice' for key 'uk_username'";
+        assert_eq!(unique_violation_field(message), "username");
+        let message = "Duplicate entry '1-2' for key 'user_follow.uk_follow'";
+        assert_eq!(unique_violation_field(message), "follow");
+    }
+
+    #[test]
+    fn reads_the_field_from_postgres_constraints() {
+        let message = "duplicate key value violates unique constraint \"uk_email\"";
+        assert_eq!(unique_violation_field(message), "email");
+        let message = "duplicate key value violates unique constraint \"uk_follow\"";
+        assert_eq!(unique_violation_field(message), "follow");
+    }
+
+    #[test]
+    fn reads_the_field_from_sqlite_columns() {
+        let message = "UNIQUE constraint failed: user.email";
+        assert_eq!(unique_violation_field(message), "email");
+        let message =
+            "UNIQUE constraint failed: user_follow.follower_user_id, user_follow.followee_user_id";
+        assert_eq!(unique_violation_field(message), "follow");
+        let message =
+            "UNIQUE constraint failed: article_favorite.user_id, article_favorite.article_id";
+        assert_eq!(unique_violation_field(message), "favorite");
+    }
+
+    #[test]
+    fn falls_back_to_record_for_unknown_messages() {
+        assert_eq!(unique_violation_field("something went wrong"), "record");
+        assert_eq!(unique_violation_field(""), "record");
+    }
+}
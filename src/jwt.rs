@@ -0,0 +1,199 @@
+//! Signing and verification of access tokens.
+//!
+//! Every token names the key that signed it in its `kid` header. One
+//! configured key signs new tokens and all of them verify, so keys can be
+//! rotated without logging anybody out: publish the new key, switch
+//! `jwt.signing_key` to it, and drop the old key once its last tokens have
+//! expired. Public keys are served as a JWK set so other services can verify
+//! tokens without sharing a secret.
+
+use std::collections::HashMap;
+use std::fs;
+
+use data_encoding::BASE64URL_NOPAD;
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, EllipticCurveKeyParameters,
+    EllipticCurveKeyType, Jwk, JwkSet, KeyAlgorithm, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use rsa::pkcs8::DecodePublicKey;
+use rsa::traits::PublicKeyParts;
+use rsa::RsaPublicKey;
+use spki::{Document, ObjectIdentifier, SubjectPublicKeyInfoRef};
+
+use crate::config::{ConfigError, JwtConfig, JwtKeyConfig};
+use crate::error::{AppError, TokenError};
+use crate::models::Claims;
+
+/// `kid` of the key made from `jwt.secret` when no `jwt.keys` are configured.
+pub const DEFAULT_KEY_ID: &str = "default";
+
+const EC_PUBLIC_KEY: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+const P256: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.3.1.7");
+
+pub struct JwtKeys {
+    signing_key_id: String,
+    signing_algorithm: Algorithm,
+    encoding_key: EncodingKey,
+    /// `kid` → algorithm and key that verify tokens carrying it.
+    decoding_keys: HashMap<String, (Algorithm, DecodingKey)>,
+    jwks: JwkSet,
+}
+
+impl JwtKeys {
+    /// Reads the configured keys. The configuration must be validated.
+    pub fn load(config: &JwtConfig) -> Result<JwtKeys, ConfigError> {
+        let default_key;
+        let (keys, signing_key) = match config.signing_key_config() {
+            Some(signing_key) => (config.keys.as_slice(), signing_key),
+            None => {
+                default_key = [JwtKeyConfig {
+                    id: DEFAULT_KEY_ID.to_string(),
+                    algorithm: Algorithm::HS256,
+                    secret: Some(config.secret.clone()),
+                    private_key_file: None,
+                    public_key_file: None,
+                }];
+                (&default_key[..], &default_key[0])
+            }
+        };
+
+        let mut decoding_keys = HashMap::new();
+        let mut jwks = JwkSet { keys: Vec::new() };
+        for key in keys {
+            let decoding_key = match key.algorithm {
+                Algorithm::HS256 => DecodingKey::from_secret(secret(key).as_bytes()),
+                _ => {
+                    let path = key.public_key_file.as_deref().unwrap_or_default();
+                    let pem = read_pem(path)?;
+                    let jwk = public_jwk(key, &pem).map_err(|e| invalid_key(key, path, e))?;
+                    jwks.keys.push(jwk);
+                    match key.algorithm {
+                        Algorithm::RS256 => DecodingKey::from_rsa_pem(pem.as_bytes()),
+                        _ => DecodingKey::from_ec_pem(pem.as_bytes()),
+                    }
+                    .map_err(|e| invalid_key(key, path, e.to_string()))?
+                }
+            };
+            decoding_keys.insert(key.id.clone(), (key.algorithm, decoding_key));
+        }
+
+        let encoding_key = match signing_key.algorithm {
+            Algorithm::HS256 => EncodingKey::from_secret(secret(signing_key).as_bytes()),
+            algorithm => {
+                let path = signing_key.private_key_file.as_deref().unwrap_or_default();
+                let pem = read_pem(path)?;
+                match algorithm {
+                    Algorithm::RS256 => EncodingKey::from_rsa_pem(pem.as_bytes()),
+                    _ => EncodingKey::from_ec_pem(pem.as_bytes()),
+                }
+                .map_err(|e| invalid_key(signing_key, path, e.to_string()))?
+            }
+        };
+
+        Ok(JwtKeys {
+            signing_key_id: signing_key.id.clone(),
+            signing_algorithm: signing_key.algorithm,
+            encoding_key,
+            decoding_keys,
+            jwks,
+        })
+    }
+
+    /// Signs `claims` with the signing key.
+    pub fn encode(&self, claims: &Claims) -> Result<String, AppError> {
+        let mut header = Header::new(self.signing_algorithm);
+        header.kid = Some(self.signing_key_id.clone());
+        jsonwebtoken::encode(&header, claims, &self.encoding_key)
+            .map_err(|e| AppError::Internal(format!("could not sign token: {}", e)))
+    }
+
+    /// Verifies `token` with the key its `kid` names. The algorithm comes
+    /// from that key, never from the token.
+    pub fn decode(&self, token: &str) -> Result<Claims, AppError> {
+        let invalid = |message| AppError::Unauthenticated(TokenError::Invalid(message));
+        let header = jsonwebtoken::decode_header(token).map_err(|_| invalid("invalid token"))?;
+        let (algorithm, key) = header
+            .kid
+            .and_then(|kid| self.decoding_keys.get(&kid))
+            .ok_or_else(|| invalid("unknown signing key"))?;
+        jsonwebtoken::decode::<Claims>(token, key, &Validation::new(*algorithm))
+            .map(|data| data.claims)
+            .map_err(|e| match e.kind() {
+                ErrorKind::ExpiredSignature => invalid("token expired"),
+                _ => invalid("invalid token"),
+            })
+    }
+
+    /// The public keys, for `/.well-known/jwks.json`. HS256 keys are secret
+    /// and never listed.
+    pub fn jwks(&self) -> &JwkSet {
+        &self.jwks
+    }
+}
+
+fn secret(key: &JwtKeyConfig) -> &str {
+    key.secret.as_deref().unwrap_or_default()
+}
+
+fn read_pem(path: &str) -> Result<String, ConfigError> {
+    fs::read_to_string(path).map_err(|e| ConfigError::Io(path.to_string(), e))
+}
+
+fn invalid_key(key: &JwtKeyConfig, path: &str, message: String) -> ConfigError {
+    ConfigError::Invalid(
+        "jwt.keys",
+        format!("key {:?}: {}: {}", key.id, path, message),
+    )
+}
+
+/// The JWK for an RS256 or ES256 (P-256) public key in SubjectPublicKeyInfo
+/// PEM form.
+fn public_jwk(key: &JwtKeyConfig, pem: &str) -> Result<Jwk, String> {
+    let (key_algorithm, algorithm) = match key.algorithm {
+        Algorithm::RS256 => {
+            let public_key = RsaPublicKey::from_public_key_pem(pem).map_err(|e| e.to_string())?;
+            let parameters = RSAKeyParameters {
+                key_type: RSAKeyType::RSA,
+                n: BASE64URL_NOPAD.encode(&public_key.n().to_bytes_be()),
+                e: BASE64URL_NOPAD.encode(&public_key.e().to_bytes_be()),
+            };
+            (KeyAlgorithm::RS256, AlgorithmParameters::RSA(parameters))
+        }
+        _ => {
+            let (_, document) = Document::from_pem(pem).map_err(|e| e.to_string())?;
+            let info = SubjectPublicKeyInfoRef::try_from(document.as_bytes())
+                .map_err(|e| e.to_string())?;
+            if info.algorithm.oid != EC_PUBLIC_KEY
+                || info.algorithm.parameters_oid().ok() != Some(P256)
+            {
+                return Err("not a P-256 public key".to_string());
+            }
+            // 未压缩的点：0x04 || x || y
+            let point = match info.subject_public_key.as_bytes() {
+                Some(point) if point.len() == 65 && point[0] == 4 => point,
+                _ => return Err("expected an uncompressed P-256 point".to_string()),
+            };
+            let parameters = EllipticCurveKeyParameters {
+                key_type: EllipticCurveKeyType::EC,
+                curve: EllipticCurve::P256,
+                x: BASE64URL_NOPAD.encode(&point[1..33]),
+                y: BASE64URL_NOPAD.encode(&point[33..]),
+            };
+            (
+                KeyAlgorithm::ES256,
+                AlgorithmParameters::EllipticCurve(parameters),
+            )
+        }
+    };
+    Ok(Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(key_algorithm),
+            key_id: Some(key.id.clone()),
+            ..CommonParameters::default()
+        },
+        algorithm,
+    })
+}
@@ -1,84 +1,252 @@
-use std::fmt;
-
+use crate::config::Config;
+use crate::error::{AppError, TokenError};
+use crate::jwt::JwtKeys;
 use crate::models::Claims;
+use crate::persistence::Repository;
 use actix_web::dev::Payload;
-use actix_web::{FromRequest, HttpRequest, HttpResponse, ResponseError};
-use futures::future::{err, ok, Ready};
-use jsonwebtoken::{decode, DecodingKey, Validation};
-use serde::{Deserialize, Serialize};
+use actix_web::http::header;
+use actix_web::{web, FromRequest, HttpMessage, HttpRequest};
+use chrono::Utc;
+use futures::future::{ready, LocalBoxFuture};
 
-mod models;
+pub mod authorization;
+pub mod config;
+pub mod error;
+pub mod jwt;
+pub mod models;
+pub mod persistence;
+pub mod rate_limit;
+pub mod routes;
+pub mod search;
+pub mod slug;
+pub mod utils;
+pub mod validation;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ServiceError {
-    pub errors: ErrorsBody,
+#[derive(Debug, Clone)]
+pub struct SessionState {
+    pub user_id: i64,
+    /// The login session the access token belongs to.
+    pub session_id: i64,
+    pub token: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ErrorsBody {
-    body: Vec<String>,
+/// Cookie browser clients get the access token in when `jwt.cookie` is on.
+pub const ACCESS_TOKEN_COOKIE: &str = "access_token";
+
+impl FromRequest for SessionState {
+    type Error = AppError;
+    type Future = LocalBoxFuture<'static, actix_web::Result<SessionState, AppError>>;
+    // type Config = ();
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let session_state = SessionState::authenticate(req);
+        Box::pin(async move {
+            session_state
+                .await?
+                .ok_or(AppError::Unauthenticated(TokenError::Missing))
+        })
+    }
 }
 
-impl ServiceError {
-    pub fn new(msg: String) -> Self {
-        ServiceError {
-            errors: ErrorsBody { body: vec![msg] },
+impl SessionState {
+    /// Verifies the request's access token and its session; `None` when the
+    /// request carries no token at all. The result is reused if middleware
+    /// already authenticated the request.
+    pub(crate) fn authenticate(
+        req: &HttpRequest,
+    ) -> LocalBoxFuture<'static, Result<Option<SessionState>, AppError>> {
+        if let Some(session_state) = req.extensions().get::<SessionState>().cloned() {
+            return Box::pin(ready(Ok(Some(session_state))));
         }
+        let repo = req.app_data::<web::Data<dyn Repository>>().cloned();
+        let decoded = match extract_token(req) {
+            Ok(Some(token)) => decode_token(req, token),
+            Ok(None) => return Box::pin(ready(Ok(None))),
+            Err(e) => Err(e),
+        };
+        Box::pin(async move {
+            let (claims, token) = decoded?;
+            let repo = repo.ok_or_else(|| AppError::Internal("missing repository!".to_string()))?;
+            // 签名有效还不够，会话被注销（logout）后令牌立即失效
+            let session = match repo.select_session_by_id(claims.sid).await {
+                Ok(session) => session,
+                Err(AppError::NotFound(_)) => {
+                    return Err(AppError::Unauthenticated(TokenError::Invalid(
+                        "session not found",
+                    )))
+                }
+                Err(e) => return Err(e),
+            };
+            if session.user_id != claims.sub || !session.is_active(Utc::now().naive_utc()) {
+                return Err(AppError::Unauthenticated(TokenError::Invalid(
+                    "session revoked",
+                )));
+            }
+            Ok(Some(SessionState {
+                user_id: claims.sub,
+                session_id: session.id,
+                token,
+            }))
+        })
     }
 }
 
-impl ResponseError for ServiceError {
-    // fn status_code(&self) -> actix_web::http::StatusCode {
-    //     actix_web::http::StatusCode::UNAUTHORIZED
-    // }
-    fn error_response(&self) -> actix_web::HttpResponse {
-        HttpResponse::Unauthorized().json(self)
+/// The access token from `Authorization: Token <jwt>` or `Bearer <jwt>`
+/// (scheme case-insensitive), else from the access token cookie when
+/// `jwt.cookie` is on. `Ok(None)` when the request carries neither.
+fn extract_token(req: &HttpRequest) -> Result<Option<String>, AppError> {
+    let malformed = |message| AppError::Unauthenticated(TokenError::Malformed(message));
+    let value = match req.headers().get(header::AUTHORIZATION) {
+        Some(value) => value,
+        None => {
+            let cookie_enabled = req
+                .app_data::<web::Data<Config>>()
+                .is_some_and(|config| config.jwt.cookie);
+            return Ok(req
+                .cookie(ACCESS_TOKEN_COOKIE)
+                .filter(|_| cookie_enabled)
+                .map(|cookie| cookie.value().to_string())
+                .filter(|token| !token.is_empty()));
+        }
+    };
+    let value = value
+        .to_str()
+        .map_err(|_| malformed("authorization header must be ASCII"))?;
+    let (scheme, token) = value
+        .trim()
+        .split_once(|c: char| c.is_ascii_whitespace())
+        .ok_or_else(|| malformed("expected `Token <jwt>` or `Bearer <jwt>`"))?;
+    if !scheme.eq_ignore_ascii_case("token") && !scheme.eq_ignore_ascii_case("bearer") {
+        return Err(malformed("unsupported authorization scheme"));
+    }
+    let token = token.trim();
+    if token.is_empty() || token.contains(|c: char| c.is_ascii_whitespace()) {
+        return Err(malformed("expected `Token <jwt>` or `Bearer <jwt>`"));
     }
+    Ok(Some(token.to_string()))
 }
 
-impl fmt::Display for ServiceError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self.errors)
-    }
+/// Verifies `token` and returns its claims along with the token itself.
+fn decode_token(req: &HttpRequest, token: String) -> Result<(Claims, String), AppError> {
+    let keys = match req.app_data::<web::Data<JwtKeys>>() {
+        Some(keys) => keys,
+        None => return Err(AppError::Internal("missing jwt keys!".to_string())),
+    };
+    let claims = keys.decode(&token)?;
+    Ok((claims, token))
 }
 
+/// Optional authentication for public endpoints: `None` when the request
+/// carries no access token, an error if it carries an invalid one.
 #[derive(Debug, Clone)]
-pub struct SessionState {
-    pub user_id: i64,
-    pub token: String,
+pub struct MaybeAuthenticated(pub Option<SessionState>);
+
+impl MaybeAuthenticated {
+    pub fn user_id(&self) -> Option<i64> {
+        self.0.as_ref().map(|s| s.user_id)
+    }
 }
 
-impl FromRequest for SessionState {
-    type Error = ServiceError;
-    type Future = Ready<actix_web::Result<SessionState, ServiceError>>;
-    // type Config = ();
+impl FromRequest for MaybeAuthenticated {
+    type Error = AppError;
+    type Future = LocalBoxFuture<'static, actix_web::Result<MaybeAuthenticated, AppError>>;
 
     fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
-        let auth = req.headers().get("Authorization");
-        // log::info!("Authorization: {:?}", auth);
-
-        match auth {
-            Some(auth) => {
-                let _split: Vec<&str> = auth.to_str().unwrap().split("Token").collect();
-                let token = _split[1].trim();
-
-                let token_data = decode::<Claims>(
-                    token,
-                    &DecodingKey::from_secret("realworld".as_ref()),
-                    &Validation::default(),
-                );
-                match token_data {
-                    Ok(token_data) => {
-                        let user_id = token_data.claims.sub;
-                        ok(SessionState {
-                            user_id,
-                            token: token.to_string(),
-                        })
-                    }
-                    Err(_e) => err(ServiceError::new("invalid token!".to_string())),
-                }
-            }
-            None => err(ServiceError::new("invalid authorization header!".to_string())),
+        let session_state = SessionState::authenticate(req);
+        Box::pin(async move { Ok(MaybeAuthenticated(session_state.await?)) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::cookie::Cookie;
+    use actix_web::http::header::HeaderValue;
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    fn with_cookie_config(req: TestRequest, cookie: bool) -> TestRequest {
+        let mut config = Config::default();
+        config.jwt.cookie = cookie;
+        req.app_data(web::Data::new(config))
+    }
+
+    fn extract(req: TestRequest) -> Result<Option<String>, AppError> {
+        extract_token(&req.to_http_request())
+    }
+
+    fn authorization(value: &'static str) -> TestRequest {
+        TestRequest::default().insert_header((header::AUTHORIZATION, value))
+    }
+
+    #[test]
+    fn accepts_token_and_bearer_in_any_case() {
+        for value in [
+            "Token abc.def.ghi",
+            "Bearer abc.def.ghi",
+            "token abc.def.ghi",
+            "BEARER abc.def.ghi",
+            "  Bearer   abc.def.ghi  ",
+        ] {
+            let token = extract(authorization(value)).unwrap_or_else(|_| panic!("{:?}", value));
+            assert_eq!(token.as_deref(), Some("abc.def.ghi"), "{:?}", value);
         }
     }
+
+    #[test]
+    fn rejects_malformed_headers() {
+        for value in [
+            "abc.def.ghi",
+            "Basic abc.def.ghi",
+            "Bearer",
+            "Bearer ",
+            "Bearer abc def",
+        ] {
+            assert!(
+                matches!(
+                    extract(authorization(value)),
+                    Err(AppError::Unauthenticated(TokenError::Malformed(_)))
+                ),
+                "{:?}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_non_ascii_headers() {
+        let value = HeaderValue::from_bytes("Token abc.déf".as_bytes()).unwrap();
+        let req = TestRequest::default().insert_header((header::AUTHORIZATION, value));
+        assert!(matches!(
+            extract(req),
+            Err(AppError::Unauthenticated(TokenError::Malformed(_)))
+        ));
+    }
+
+    #[test]
+    fn no_header_and_no_cookie_is_no_token() {
+        assert!(matches!(extract(TestRequest::default()), Ok(None)));
+    }
+
+    #[test]
+    fn reads_the_cookie_only_when_enabled() {
+        let req = || TestRequest::default().cookie(Cookie::new(ACCESS_TOKEN_COOKIE, "abc.def.ghi"));
+        let token = extract(with_cookie_config(req(), true)).ok().flatten();
+        assert_eq!(token.as_deref(), Some("abc.def.ghi"));
+        let token = extract(with_cookie_config(req(), false)).ok().flatten();
+        assert_eq!(token, None);
+        assert_eq!(extract(req()).ok().flatten(), None);
+
+        // 请求头优先于 Cookie
+        let req = with_cookie_config(req(), true)
+            .insert_header((header::AUTHORIZATION, "Token from.the.header"));
+        let token = extract(req).ok().flatten();
+        assert_eq!(token.as_deref(), Some("from.the.header"));
+    }
+
+    #[test]
+    fn ignores_an_empty_cookie() {
+        let req = TestRequest::default().cookie(Cookie::new(ACCESS_TOKEN_COOKIE, ""));
+        assert!(matches!(extract(with_cookie_config(req, true)), Ok(None)));
+    }
 }
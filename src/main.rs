@@ -1,84 +1,85 @@
 use std::env;
+use std::sync::Arc;
 
 use actix_cors::Cors;
 
 use actix_web::{middleware::Logger, web, App, HttpServer};
 use dotenvy::dotenv;
 use env_logger::Env;
-use sqlx::mysql::MySqlPoolOptions;
-use sqlx::MySqlPool;
+use realworld_rust_actix_web::config::{Config, CorsConfig};
+use realworld_rust_actix_web::jwt::JwtKeys;
+use realworld_rust_actix_web::persistence;
+use realworld_rust_actix_web::rate_limit::{LoginLimiter, MemoryStore, RateLimits};
+use realworld_rust_actix_web::routes;
 
-mod models;
-mod persistence;
-mod routes;
-mod utils;
-
-async fn get_conn_builder() -> MySqlPool {
-    // let num_cores = num_cpus::get();
-
-    let database_url = env::var("DATABASE_URL").expect("database url is empty!!!");
-    // We create a single connection pool for SQLx that's shared across the whole application.
-    // This saves us from opening a new connection for every API call, which is wasteful.
-    MySqlPoolOptions::new()
-        // .min_connections(num_cores as u32 / 2)
-        // .max_connections(num_cores as u32)
-        .connect(&database_url)
-        .await
-        .expect("could not connect to database_url")
+fn build_cors(config: &CorsConfig) -> Cors {
+    // 任意来源不带凭据：令牌只能通过 Authorization 头发送
+    if config.allowed_origins.iter().any(|o| o == "*") {
+        return Cors::default()
+            .allow_any_origin()
+            .allow_any_method()
+            .allow_any_header()
+            .max_age(3600);
+    }
+    config
+        .allowed_origins
+        .iter()
+        .fold(Cors::default(), |cors, origin| cors.allowed_origin(origin))
+        .allow_any_method()
+        .allow_any_header()
+        .supports_credentials()
+        .max_age(3600)
 }
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv().ok();
 
-    env_logger::init_from_env(Env::default().default_filter_or("info"));
+    let config = Config::load().unwrap_or_else(|e| panic!("invalid configuration: {}", e));
+
+    env_logger::init_from_env(Env::default().default_filter_or(config.log.level.as_str()));
+
+    let jwt_keys =
+        JwtKeys::load(&config.jwt).unwrap_or_else(|e| panic!("invalid configuration: {}", e));
 
     log::info!("initializing database connection");
 
-    // let i = Box::new(5);
+    // `realworld-rust-actix-web migrate` 只执行迁移然后退出
+    let migrate_only = env::args().nth(1).as_deref() == Some("migrate");
+
+    let repository = persistence::connect(
+        &config.database,
+        config.database.auto_migrate || migrate_only,
+    )
+    .await
+    .expect("could not connect to database_url");
 
-    let pool = get_conn_builder().await;
+    if migrate_only {
+        log::info!("database migrations applied");
+        return Ok(());
+    }
 
-    let pool_data = web::Data::new(pool);
+    let bind_address = config.server.bind_address.clone();
+    let repository_data = web::Data::from(repository);
+    let config_data = web::Data::new(config);
+    let jwt_keys_data = web::Data::new(jwt_keys);
+    let login_limiter_data = web::Data::new(LoginLimiter::new(&config_data.login));
+    let rate_limits_data = web::Data::new(RateLimits::new(
+        &config_data,
+        Arc::new(MemoryStore::default()),
+    ));
     HttpServer::new(move || {
         App::new()
-            .app_data(pool_data.clone())
+            .app_data(repository_data.clone())
+            .app_data(config_data.clone())
+            .app_data(jwt_keys_data.clone())
+            .app_data(login_limiter_data.clone())
+            .app_data(rate_limits_data.clone())
             .wrap(Logger::default())
-            .wrap(Cors::permissive())
-            .service(
-                // 不需要登录的服务
-                web::scope("/api/users")
-                    .service(routes::users::login_user)
-                    .service(routes::users::registry_user),
-            )
-            .service(
-                web::scope("/api/articles")
-                    .service(routes::articles::list_articles)
-                    .service(routes::articles::create_article)
-                    .service(routes::articles::delete_article)
-                    .service(routes::articles::update_article)
-                    .service(routes::articles::list_articles_feed)
-                    .service(routes::articles::single_article)
-                    .service(routes::comments::get_article_comments)
-                    .service(routes::comments::create_article_comments)
-                    .service(routes::comments::delete_article_comment)
-                    .service(routes::articles::favorite_article)
-                    .service(routes::articles::unfavorite_article),
-            )
-            .service(
-                web::scope("/api/user")
-                    .service(routes::users::current_user)
-                    .service(routes::users::update_user),
-            )
-            .service(
-                web::scope("/api/profiles")
-                    .service(routes::profiles::follow_user)
-                    .service(routes::profiles::delete_follow_user)
-                    .service(routes::profiles::get_profile),
-            )
-            .service(web::scope("/api/tags").service(routes::tags::all_tags))
+            .wrap(build_cors(&config_data.cors))
+            .configure(routes::configure)
     })
-    .bind(("127.0.0.1", 3000))?
+    .bind(bind_address)?
     .run()
     .await
 }
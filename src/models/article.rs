@@ -1,7 +1,19 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use actix_web::dev::Payload;
+use actix_web::{web, FromRequest, HttpRequest};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
+use data_encoding::BASE64URL_NOPAD;
+use futures::future::{ready, Ready};
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
+use validator::{Validate, ValidationErrors};
+
+use crate::error::AppError;
+use crate::validation::normalize_tag;
 
-use super::user::UserResponse;
+use super::ProfileResponse;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ArticlesWrapper<T>
@@ -10,7 +22,13 @@ where
 {
     pub articles: Vec<T>,
     #[serde(rename = "articlesCount")]
-    pub articles_count: u32,
+    pub articles_count: i64,
+    /// Pass as `after` to fetch the next page in `sort` order.
+    #[serde(rename = "nextCursor", skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    /// Pass as `before` to fetch the previous page in `sort` order.
+    #[serde(rename = "prevCursor", skip_serializing_if = "Option::is_none")]
+    pub prev_cursor: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -21,34 +39,354 @@ where
     pub article: T,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+impl<T> Validate for ArticleWrapper<T>
+where
+    T: serde::Serialize + Validate,
+{
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        self.article.validate()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
 pub struct ArticleCreateForm {
+    #[validate(
+        custom = "crate::validation::not_blank",
+        length(max = 255, message = "is too long (maximum is 255 characters)")
+    )]
     pub title: String,
+    #[validate(
+        custom = "crate::validation::not_blank",
+        length(max = 1024, message = "is too long (maximum is 1024 characters)")
+    )]
     pub description: String,
+    #[validate(
+        custom = "crate::validation::not_blank",
+        length(max = 100000, message = "is too long (maximum is 100000 characters)")
+    )]
     pub body: String,
-    #[serde(rename = "tagList")]
+    #[serde(
+        rename = "tagList",
+        default,
+        deserialize_with = "crate::validation::normalized_tags"
+    )]
+    #[validate(custom = "crate::validation::tag_list")]
     pub tag_list: Vec<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Validate)]
 pub struct ArticleUpdateForm {
+    #[validate(
+        custom = "crate::validation::not_blank",
+        length(max = 255, message = "is too long (maximum is 255 characters)")
+    )]
     pub title: Option<String>,
+    #[validate(
+        custom = "crate::validation::not_blank",
+        length(max = 1024, message = "is too long (maximum is 1024 characters)")
+    )]
     pub description: Option<String>,
+    #[validate(
+        custom = "crate::validation::not_blank",
+        length(max = 100000, message = "is too long (maximum is 100000 characters)")
+    )]
     pub body: Option<String>,
-    #[serde(rename = "tagList")]
+    #[serde(
+        rename = "tagList",
+        default,
+        deserialize_with = "crate::validation::normalized_optional_tags"
+    )]
+    #[validate(custom = "crate::validation::tag_list")]
     pub tag_list: Option<Vec<String>>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// Filters and paging of the article list, parsed by hand from the query
+/// string because `tag` and `author` may repeat (`?tag=a&tag=b`), which
+/// `web::Query` cannot express.
+#[derive(Debug, Default)]
 pub struct ArticleQuery {
+    /// Articles carrying any of these tags, or all of them with `tag_match=all`.
+    pub tags: Vec<String>,
+    pub tag_match: TagMatch,
+    /// Articles written by any of these usernames.
+    pub authors: Vec<String>,
+    pub favorited: Option<String>,
+    /// Articles created at or after this time.
+    pub since: Option<NaiveDateTime>,
+    /// Articles created strictly before this time.
+    pub until: Option<NaiveDateTime>,
+    /// Leaves out articles written by `viewer_id`.
+    pub exclude_own: bool,
+
+    /// Page size, 20 by default and at most 100.
+    pub limit: Option<i32>,
+    pub offset: Option<i32>,
+
+    /// Keyset pagination: articles strictly after this cursor in `sort`
+    /// order (older for `latest`, newer for `oldest`). Takes precedence over
+    /// `offset`.
+    pub after: Option<String>,
+    /// Keyset pagination: articles strictly before this cursor in `sort` order.
+    pub before: Option<String>,
+    /// One of the `ArticleSort` names; `latest` when absent.
+    pub sort: Option<String>,
+
+    /// Set by the handler, not the query string.
+    pub viewer_id: Option<i64>,
+    pub feed_user_id: Option<i64>,
+}
+
+/// Most values accepted for a repeatable filter.
+const MAX_FILTER_VALUES: usize = 10;
+
+impl ArticleQuery {
+    /// Builds the query from decoded `key=value` pairs. Empty values count as
+    /// absent. Only `tag` and `author` may repeat; unknown keys and repeated
+    /// single-value keys are rejected rather than silently widening the list.
+    pub fn from_pairs(pairs: Vec<(String, String)>) -> Result<Self, AppError> {
+        let mut query = ArticleQuery::default();
+        let mut given = HashSet::new();
+        for (key, value) in pairs {
+            if value.is_empty() {
+                continue;
+            }
+            let repeatable = matches!(key.as_str(), "tag" | "author");
+            match key.as_str() {
+                "tag" => push_unique(&mut query.tags, "tag", normalize_tag(&value))?,
+                "author" => push_unique(&mut query.authors, "author", value)?,
+                "tag_match" => query.tag_match = value.parse()?,
+                "favorited" => query.favorited = Some(value),
+                "since" => query.since = Some(parse_time("since", &value)?),
+                "until" => query.until = Some(parse_time("until", &value)?),
+                "exclude_own" => query.exclude_own = parse_bool("exclude_own", &value)?,
+                "limit" => query.limit = Some(parse_number("limit", &value)?),
+                "offset" => query.offset = Some(parse_number("offset", &value)?),
+                "after" => query.after = Some(value),
+                "before" => query.before = Some(value),
+                "sort" => query.sort = Some(value),
+                _ => {
+                    return Err(AppError::validation(
+                        "query",
+                        &format!("has an unknown parameter {:?}", key),
+                    ))
+                }
+            }
+            if !repeatable && !given.insert(key.clone()) {
+                return Err(AppError::validation(&key, "can't be given more than once"));
+            }
+        }
+        if let (Some(since), Some(until)) = (query.since, query.until) {
+            if since >= until {
+                return Err(AppError::validation("until", "must be later than since"));
+            }
+        }
+        Ok(query)
+    }
+}
+
+impl FromRequest for ArticleQuery {
+    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(
+            web::Query::<Vec<(String, String)>>::from_query(req.query_string())
+                .map_err(|e| AppError::validation("query", &e.to_string()))
+                .and_then(|pairs| ArticleQuery::from_pairs(pairs.into_inner())),
+        )
+    }
+}
+
+fn push_unique(values: &mut Vec<String>, field: &str, value: String) -> Result<(), AppError> {
+    if values.contains(&value) {
+        return Ok(());
+    }
+    if values.len() == MAX_FILTER_VALUES {
+        return Err(AppError::validation(
+            field,
+            &format!("can't be given more than {} times", MAX_FILTER_VALUES),
+        ));
+    }
+    values.push(value);
+    Ok(())
+}
+
+/// An RFC 3339 timestamp, or a bare `YYYY-MM-DD` meaning midnight UTC.
+fn parse_time(field: &str, value: &str) -> Result<NaiveDateTime, AppError> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.naive_utc());
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_time(NaiveTime::MIN))
+        .map_err(|_| AppError::validation(field, "must be a date or an RFC 3339 timestamp"))
+}
+
+fn parse_bool(field: &str, value: &str) -> Result<bool, AppError> {
+    match value {
+        "true" | "1" => Ok(true),
+        "false" | "0" => Ok(false),
+        _ => Err(AppError::validation(field, "must be true or false")),
+    }
+}
+
+fn parse_number(field: &str, value: &str) -> Result<i32, AppError> {
+    value
+        .parse()
+        .map_err(|_| AppError::validation(field, "must be a number"))
+}
+
+/// How several `tag` filters combine.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TagMatch {
+    /// Articles with at least one of the tags.
+    #[default]
+    Any,
+    /// Articles with every one of the tags.
+    All,
+}
+
+impl FromStr for TagMatch {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "any" => Ok(TagMatch::Any),
+            "all" => Ok(TagMatch::All),
+            _ => Err(AppError::validation("tag_match", "must be one of any, all")),
+        }
+    }
+}
+
+/// Orderings accepted by the `sort` parameter. The `order by` clause is only
+/// ever chosen by matching on this enum, never built from the raw parameter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArticleSort {
+    /// `created_at desc, id desc`.
+    #[default]
+    Latest,
+    /// `created_at asc, id asc`.
+    Oldest,
+    MostFavorited,
+    MostCommented,
+    /// Favorites weighted by how recently they were given.
+    Trending,
+}
+
+impl ArticleSort {
+    pub const ALL: [ArticleSort; 5] = [
+        ArticleSort::Latest,
+        ArticleSort::Oldest,
+        ArticleSort::MostFavorited,
+        ArticleSort::MostCommented,
+        ArticleSort::Trending,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ArticleSort::Latest => "latest",
+            ArticleSort::Oldest => "oldest",
+            ArticleSort::MostFavorited => "most_favorited",
+            ArticleSort::MostCommented => "most_commented",
+            ArticleSort::Trending => "trending",
+        }
+    }
+
+    /// Only the chronological orders have a keyset a cursor can encode.
+    pub fn supports_cursor(self) -> bool {
+        matches!(self, ArticleSort::Latest | ArticleSort::Oldest)
+    }
+}
+
+impl FromStr for ArticleSort {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ArticleSort::ALL
+            .into_iter()
+            .find(|sort| sort.as_str() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = ArticleSort::ALL.iter().map(|sort| sort.as_str()).collect();
+                AppError::validation("sort", &format!("must be one of {}", names.join(", ")))
+            })
+    }
+}
+
+/// Query parameters of `GET /api/articles/search`. Results are ordered by
+/// relevance, so they page by `offset` only.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ArticleSearchQuery {
+    #[serde(default)]
+    pub q: String,
     pub tag: Option<String>,
     pub author: Option<String>,
     pub favorited: Option<String>,
 
     pub limit: Option<i32>,
     pub offset: Option<i32>,
+}
 
-    pub feed_user_id: Option<i64>,
+impl ArticleSearchQuery {
+    /// The filters and paging as an `ArticleQuery`.
+    pub fn to_article_query(&self) -> ArticleQuery {
+        ArticleQuery {
+            tags: self.tag.as_deref().map(normalize_tag).into_iter().collect(),
+            authors: self.author.clone().into_iter().collect(),
+            favorited: self.favorited.clone(),
+            limit: self.limit,
+            offset: self.offset,
+            ..ArticleQuery::default()
+        }
+    }
+}
+
+/// One page of articles as returned by `select_articles_by_query`.
+#[derive(Debug)]
+pub struct ArticlePage {
+    pub articles: Vec<ArticleEntity>,
+    pub articles_count: i64,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+}
+
+/// Position of an article in `created_at, id` order, encoded as an opaque
+/// base64url token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArticleCursor {
+    pub created_at: NaiveDateTime,
+    pub id: i64,
+}
+
+const CURSOR_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.6f";
+
+impl ArticleCursor {
+    pub fn of(article: &ArticleEntity) -> Self {
+        ArticleCursor {
+            created_at: article.created_at,
+            id: article.id,
+        }
+    }
+
+    pub fn created_at_param(&self) -> String {
+        self.created_at.format(CURSOR_TIME_FORMAT).to_string()
+    }
+
+    pub fn encode(&self) -> String {
+        BASE64URL_NOPAD.encode(format!("{}|{}", self.created_at_param(), self.id).as_bytes())
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, AppError> {
+        let invalid = || AppError::validation("cursor", "is invalid");
+        let raw = BASE64URL_NOPAD
+            .decode(cursor.as_bytes())
+            .map_err(|_| invalid())?;
+        let raw = String::from_utf8(raw).map_err(|_| invalid())?;
+        let (created_at, id) = raw.split_once('|').ok_or_else(invalid)?;
+        Ok(ArticleCursor {
+            created_at: NaiveDateTime::parse_from_str(created_at, CURSOR_TIME_FORMAT)
+                .map_err(|_| invalid())?,
+            id: id.parse().map_err(|_| invalid())?,
+        })
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -66,10 +404,30 @@ pub struct ArticleResponse {
     pub favorites_count: i64,
     #[serde(rename = "tagList")]
     pub tag_list: Vec<String>,
-    pub author: UserResponse,
+    pub author: ProfileResponse,
 }
 
-#[derive(Debug, Deserialize, Serialize, FromRow)]
+/// A search result: the article plus its matching fields with the matched
+/// words wrapped in `<mark>`. Fields without a match are omitted.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ArticleSearchResponse {
+    #[serde(flatten)]
+    pub article: ArticleResponse,
+    pub highlight: ArticleHighlight,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ArticleHighlight {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// A window of the body around the first match.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, FromRow)]
 pub struct ArticleEntity {
     pub id: i64,
     pub title: String,
@@ -92,3 +450,175 @@ pub struct ArticleFavoriteEntity {
     pub user_id: i64,
     pub article_id: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_invalid_cursor(result: Result<ArticleCursor, AppError>) -> bool {
+        matches!(result, Err(AppError::Validation(errors)) if errors.contains_key("cursor"))
+    }
+
+    fn from_pairs(pairs: &[(&str, &str)]) -> Result<ArticleQuery, AppError> {
+        ArticleQuery::from_pairs(
+            pairs
+                .iter()
+                .map(|&(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        )
+    }
+
+    fn invalid_field(result: Result<ArticleQuery, AppError>) -> Option<String> {
+        match result {
+            Err(AppError::Validation(errors)) => errors.into_keys().next(),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn repeated_tags_and_authors_accumulate() {
+        let query = from_pairs(&[
+            ("tag", "Rust"),
+            ("author", "alice"),
+            ("tag", "web"),
+            ("tag", "rust "),
+            ("author", "bob"),
+            ("tag_match", "all"),
+        ])
+        .unwrap();
+        assert_eq!(query.tags, ["rust", "web"]);
+        assert_eq!(query.authors, ["alice", "bob"]);
+        assert_eq!(query.tag_match, TagMatch::All);
+    }
+
+    #[test]
+    fn filters_combine() {
+        let query = from_pairs(&[
+            ("author", "alice"),
+            ("favorited", "bob"),
+            ("tag", "rust"),
+            ("since", "2024-01-01"),
+            ("until", "2024-02-01T00:00:00+02:00"),
+            ("exclude_own", "true"),
+            ("limit", "5"),
+            ("offset", "10"),
+        ])
+        .unwrap();
+        assert_eq!(query.authors, ["alice"]);
+        assert_eq!(query.favorited.as_deref(), Some("bob"));
+        assert_eq!(query.tags, ["rust"]);
+        assert_eq!(query.since.unwrap().to_string(), "2024-01-01 00:00:00");
+        assert_eq!(query.until.unwrap().to_string(), "2024-01-31 22:00:00");
+        assert!(query.exclude_own);
+        assert_eq!((query.limit, query.offset), (Some(5), Some(10)));
+    }
+
+    #[test]
+    fn empty_values_count_as_absent() {
+        let query = from_pairs(&[("tag", ""), ("limit", ""), ("limit", "3")]).unwrap();
+        assert!(query.tags.is_empty());
+        assert_eq!(query.limit, Some(3));
+    }
+
+    #[test]
+    fn unknown_and_repeated_single_value_keys_are_rejected() {
+        assert_eq!(
+            invalid_field(from_pairs(&[("tags", "rust")])).as_deref(),
+            Some("query")
+        );
+        for key in ["favorited", "limit", "offset", "sort", "since", "tag_match"] {
+            let value = match key {
+                "limit" | "offset" => "1",
+                "sort" => "latest",
+                "since" => "2024-01-01",
+                "tag_match" => "any",
+                _ => "alice",
+            };
+            assert_eq!(
+                invalid_field(from_pairs(&[(key, value), (key, value)])).as_deref(),
+                Some(key)
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_values_are_rejected() {
+        for (key, value) in [
+            ("limit", "ten"),
+            ("since", "yesterday"),
+            ("exclude_own", "maybe"),
+            ("tag_match", "some"),
+        ] {
+            assert_eq!(
+                invalid_field(from_pairs(&[(key, value)])).as_deref(),
+                Some(key)
+            );
+        }
+        let range = from_pairs(&[("since", "2024-02-01"), ("until", "2024-01-01")]);
+        assert_eq!(invalid_field(range).as_deref(), Some("until"));
+
+        let tags: Vec<String> = (0..=MAX_FILTER_VALUES)
+            .map(|i| format!("tag{}", i))
+            .collect();
+        let pairs: Vec<(&str, &str)> = tags.iter().map(|tag| ("tag", tag.as_str())).collect();
+        assert_eq!(invalid_field(from_pairs(&pairs)).as_deref(), Some("tag"));
+    }
+
+    #[test]
+    fn sort_accepts_only_the_whitelisted_names() {
+        for sort in ArticleSort::ALL {
+            assert_eq!(sort.as_str().parse::<ArticleSort>().unwrap(), sort);
+        }
+        for name in [
+            "",
+            "LATEST",
+            "id desc",
+            "latest; drop table article",
+            "created_at",
+        ] {
+            assert!(
+                matches!(
+                    name.parse::<ArticleSort>(),
+                    Err(AppError::Validation(errors)) if errors.contains_key("sort")
+                ),
+                "{:?}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn cursor_round_trips() {
+        let cursor = ArticleCursor {
+            created_at: NaiveDate::from_ymd_opt(2024, 4, 9)
+                .unwrap()
+                .and_hms_micro_opt(13, 5, 7, 123_456)
+                .unwrap(),
+            id: 42,
+        };
+        let encoded = cursor.encode();
+        assert!(encoded
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_'));
+        assert_eq!(ArticleCursor::decode(&encoded).unwrap(), cursor);
+    }
+
+    #[test]
+    fn malformed_cursors_are_rejected() {
+        let encode = |raw: &str| BASE64URL_NOPAD.encode(raw.as_bytes());
+        for cursor in [
+            "not base64!".to_string(),
+            BASE64URL_NOPAD.encode(&[0xff, 0xfe]),
+            encode("2024-04-09 13:05:07.123456"),
+            encode("yesterday|42"),
+            encode("2024-04-09 13:05:07.123456|forty-two"),
+            encode("|"),
+        ] {
+            assert!(
+                is_invalid_cursor(ArticleCursor::decode(&cursor)),
+                "{:?}",
+                cursor
+            );
+        }
+    }
+}
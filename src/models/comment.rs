@@ -1,8 +1,10 @@
 use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use validator::{Validate, ValidationErrors};
 
-use super::UserResponse;
+use super::ProfileResponse;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, FromRow)]
 pub struct CommentEntity {
     pub id: i64,
     pub body: String,
@@ -15,8 +17,12 @@ pub struct CommentEntity {
 }
 
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Validate)]
 pub struct CommentCreateForm {
+    #[validate(
+        custom = "crate::validation::not_blank",
+        length(max = 10000, message = "is too long (maximum is 10000 characters)")
+    )]
     pub body: String,
 }
 
@@ -28,6 +34,15 @@ where
     pub comment: T,
 }
 
+impl<T> Validate for CommentWrapper<T>
+where
+    T: serde::Serialize + Validate,
+{
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        self.comment.validate()
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct CommentsWrapper<T> {
     pub comments: Vec<T>,
@@ -41,5 +56,5 @@ pub struct CommentResponse {
     pub created_at: String,
     #[serde(rename = "updatedAt")]
     pub updated_at: String,
-    pub author: UserResponse,
+    pub author: ProfileResponse,
 }
\ No newline at end of file
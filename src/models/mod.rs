@@ -1,16 +1,19 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
-use self::user::{UserEntity, UserResponse};
+use self::user::UserEntity;
 
 pub mod article;
 pub mod comment;
+pub mod session;
 pub mod user;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: i64,
     pub exp: u64,
+    /// Id of the session the token was issued for.
+    pub sid: i64,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -38,8 +41,23 @@ pub struct TagsWrapper {
 pub struct TagEntity {
     pub id: i64,
     pub name: String,
-    pub article_id: i64,
-    pub user_id: i64,
+}
+
+/// Query parameters of `GET /api/tags`.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct TagQuery {
+    /// Only tags whose name starts with this, matched literally.
+    pub prefix: Option<String>,
+    pub limit: Option<i32>,
+}
+
+/// Query parameters of `GET /api/tags/suggest`.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct TagSuggestQuery {
+    /// What has been typed so far; matched case-insensitively.
+    #[serde(default)]
+    pub q: String,
+    pub limit: Option<i32>,
 }
 
 pub fn to_profile_response(user: UserEntity, following: bool) -> ProfileResponse {
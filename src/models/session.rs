@@ -0,0 +1,40 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use validator::Validate;
+
+/// A login session. Access tokens carry its id as `sid` and stop working once
+/// it is revoked; its refresh token, stored only as a hash, mints new ones.
+#[derive(Debug, Clone, Deserialize, Serialize, FromRow)]
+pub struct SessionEntity {
+    pub id: i64,
+    pub created_at: NaiveDateTime,
+    /// When the session was opened or last refreshed.
+    pub updated_at: NaiveDateTime,
+    pub user_id: i64,
+    pub refresh_token_hash: String,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub expires_at: NaiveDateTime,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+impl SessionEntity {
+    pub fn is_active(&self, now: NaiveDateTime) -> bool {
+        self.revoked_at.is_none() && self.expires_at > now
+    }
+}
+
+/// What a new session records about the client that opened it.
+#[derive(Debug, Clone, Default)]
+pub struct SessionDevice {
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct RefreshForm {
+    #[serde(rename = "refreshToken")]
+    #[validate(custom = "crate::validation::not_blank")]
+    pub refresh_token: String,
+}
@@ -1,5 +1,7 @@
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
+use validator::{Validate, ValidationErrors};
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct UserWrapper<T>
@@ -9,7 +11,16 @@ where
     pub user: T,
 }
 
-#[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
+impl<T> Validate for UserWrapper<T>
+where
+    T: serde::Serialize + Validate,
+{
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        self.user.validate()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, FromRow)]
 pub struct UserEntity {
     pub id: i64,
     pub username: String,
@@ -19,24 +30,71 @@ pub struct UserEntity {
     pub bio: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// A user's run of failed logins; a successful login clears it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize, FromRow)]
+pub struct LoginFailures {
+    pub failed_login_count: i32,
+    /// Logins are refused until then.
+    pub locked_until: Option<NaiveDateTime>,
+}
+
+impl LoginFailures {
+    pub fn is_locked(&self, now: NaiveDateTime) -> bool {
+        self.locked_until.is_some_and(|until| until > now)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
 pub struct UserLogin {
+    #[validate(custom = "crate::validation::not_blank")]
     pub email: String,
+    #[validate(custom = "crate::validation::not_blank")]
     pub password: String,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Validate)]
 pub struct UserRegistryForm {
+    #[validate(
+        custom = "crate::validation::username",
+        length(max = 32, message = "is too long (maximum is 32 characters)")
+    )]
     pub username: String,
+    #[validate(
+        email(message = "is invalid"),
+        length(max = 255, message = "is too long (maximum is 255 characters)")
+    )]
     pub email: String,
+    #[validate(length(
+        min = 8,
+        max = 72,
+        message = "must be between 8 and 72 characters"
+    ))]
     pub password: String,
 }
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Validate)]
 pub struct UserUpdateForm {
+    #[validate(
+        custom = "crate::validation::username",
+        length(max = 32, message = "is too long (maximum is 32 characters)")
+    )]
     pub username: Option<String>,
+    #[validate(
+        email(message = "is invalid"),
+        length(max = 255, message = "is too long (maximum is 255 characters)")
+    )]
     pub email: Option<String>,
+    #[validate(length(
+        min = 8,
+        max = 72,
+        message = "must be between 8 and 72 characters"
+    ))]
     pub password: Option<String>,
+    #[validate(length(max = 1000, message = "is too long (maximum is 1000 characters)"))]
     pub bio: Option<String>,
+    #[validate(
+        custom = "crate::validation::image_url",
+        length(max = 1024, message = "is too long (maximum is 1024 characters)")
+    )]
     pub image: Option<String>,
 }
 
@@ -45,6 +103,9 @@ pub struct UserResponse {
     pub username: String,
     pub email: String,
     pub token: Option<String>,
+    /// Only returned when a session is opened or refreshed.
+    #[serde(rename = "refreshToken", skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
     // pub password: String,
     pub bio: Option<String>,
     pub image: Option<String>,
@@ -55,13 +116,3 @@ pub struct UserFollowEntity {
     pub follower_user_id: i64,
     pub followee_user_id: i64,
 }
-
-pub fn to_author(user: UserEntity) -> UserResponse {
-    UserResponse {
-        username: user.username,
-        email: user.email,
-        token: None,
-        bio: user.bio,
-        image: user.image,
-    }
-}
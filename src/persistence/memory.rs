@@ -0,0 +1,928 @@
+//! Thread-safe in-memory backend, mainly for `actix_web::test` integration
+//! tests that should not need a running database.
+//!
+//! Responses match the SQL backends, as do their unique keys and the
+//! `NotFound`/`Conflict` errors they produce. Strings compare
+//! case-sensitively, as on SQLite and PostgreSQL; MySQL's `_ci` collation
+//! is not reproduced.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, SubsecRound, Utc};
+
+use crate::error::AppError;
+use crate::models::article::{
+    ArticleCreateForm, ArticleCursor, ArticleEntity, ArticlePage, ArticleQuery, ArticleSort,
+    ArticleUpdateForm, TagMatch,
+};
+use crate::models::comment::CommentEntity;
+use crate::models::session::{SessionDevice, SessionEntity};
+use crate::models::user::{LoginFailures, UserEntity, UserUpdateForm};
+use crate::models::TagQuery;
+use crate::persistence::{
+    trending_cutoffs, ArticleRepo, CommentRepo, FollowRepo, PageWindow, SessionRepo, TagRepo,
+    UserRepo,
+};
+use crate::utils::encrypt_password;
+use crate::{search, slug};
+
+#[derive(Debug, Default)]
+pub struct InMemoryRepository {
+    state: RwLock<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    next_id: i64,
+    users: BTreeMap<i64, UserEntity>,
+    /// `(follower_user_id, followee_user_id)`
+    follows: BTreeSet<(i64, i64)>,
+    /// `favorites_count` is filled in on read.
+    articles: BTreeMap<i64, ArticleEntity>,
+    /// `(user_id, article_id)` → when it was favorited.
+    favorites: BTreeMap<(i64, i64), NaiveDateTime>,
+    comments: BTreeMap<i64, CommentEntity>,
+    /// `(article_id, name)`, the `article_tags` join keyed by tag name.
+    tags: BTreeSet<(i64, String)>,
+    /// Former slug → article id.
+    slug_history: BTreeMap<String, i64>,
+    /// Full-text index over the articles' title, description and body.
+    search_index: search::Index,
+    sessions: BTreeMap<i64, SessionEntity>,
+    /// Users with failed logins since their last successful one.
+    login_failures: BTreeMap<i64, LoginFailures>,
+}
+
+impl InMemoryRepository {
+    pub fn new() -> Self {
+        InMemoryRepository::default()
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, State> {
+        self.state.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, State> {
+        self.state.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl State {
+    fn next_id(&mut self) -> i64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn user_by_username(&self, username: &str) -> Option<&UserEntity> {
+        self.users.values().find(|u| u.username == username)
+    }
+
+    fn user_by_email(&self, email: &str) -> Option<&UserEntity> {
+        self.users.values().find(|u| u.email == email)
+    }
+
+    /// Enforces `uk_username` and `uk_email`, ignoring the user being updated.
+    fn check_unique_user(
+        &self,
+        id: Option<i64>,
+        username: Option<&str>,
+        email: Option<&str>,
+    ) -> Result<(), AppError> {
+        for user in self.users.values().filter(|u| Some(u.id) != id) {
+            if username == Some(user.username.as_str()) {
+                return Err(AppError::Conflict("username".to_string()));
+            }
+            if email == Some(user.email.as_str()) {
+                return Err(AppError::Conflict("email".to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    fn article(&self, article: &ArticleEntity) -> ArticleEntity {
+        let mut article = article.clone();
+        article.favorites_count = self
+            .favorites
+            .keys()
+            .filter(|(_, article_id)| *article_id == article.id)
+            .count() as i64;
+        article
+    }
+
+    /// What an article is ranked by under the non-chronological sorts.
+    fn sort_score(&self, article_id: i64, sort: ArticleSort, cutoffs: &[NaiveDateTime]) -> usize {
+        match sort {
+            ArticleSort::MostCommented => self
+                .comments
+                .values()
+                .filter(|c| c.article_id == article_id)
+                .count(),
+            ArticleSort::Trending => self
+                .favorites
+                .iter()
+                .filter(|((_, id), _)| *id == article_id)
+                .map(|(_, favorited_at)| {
+                    cutoffs
+                        .iter()
+                        .filter(|cutoff| favorited_at >= cutoff)
+                        .count()
+                })
+                .sum(),
+            _ => self
+                .favorites
+                .keys()
+                .filter(|(_, id)| *id == article_id)
+                .count(),
+        }
+    }
+
+    fn article_by_slug(&self, slug: &str) -> Option<&ArticleEntity> {
+        self.articles.values().find(|a| a.slug == slug)
+    }
+
+    /// Tag names accepted by `filter`, most used first and ties by name.
+    fn popular_tags(&self, filter: impl Fn(&str) -> bool, limit: usize) -> Vec<String> {
+        let mut counts: BTreeMap<&String, usize> = BTreeMap::new();
+        for (_, name) in self.tags.iter().filter(|(_, name)| filter(name)) {
+            *counts.entry(name).or_default() += 1;
+        }
+        // BTreeMap 已按名字排序，稳定排序后同样次数的标签仍按名字
+        let mut counts: Vec<(&String, usize)> = counts.into_iter().collect();
+        counts.sort_by_key(|&(_, count)| Reverse(count));
+        counts
+            .into_iter()
+            .take(limit)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Brings the search index up to date with the stored article.
+    fn reindex(&mut self, id: i64) {
+        match self.articles.get(&id) {
+            Some(a) => self
+                .search_index
+                .insert(id, &a.title, &a.description, &a.body),
+            None => self.search_index.remove(id),
+        }
+    }
+
+    /// Slugs in the `base` family that belong to other articles, currently or
+    /// in their history.
+    fn taken_slugs(&self, base: &str, article_id: i64) -> HashSet<String> {
+        let current = self.articles.values().map(|a| &a.slug);
+        let former = self
+            .slug_history
+            .iter()
+            .filter(|(_, id)| **id != article_id)
+            .map(|(slug, _)| slug);
+        current
+            .chain(former)
+            .filter(|slug| slug::is_derived_from(slug, base))
+            .cloned()
+            .collect()
+    }
+
+    /// Same semantics as the `where` clause the SQL backends build.
+    fn matches(&self, article: &ArticleEntity, query: &ArticleQuery) -> bool {
+        if !query.authors.is_empty()
+            && !query.authors.iter().any(|author| {
+                self.user_by_username(author)
+                    .is_some_and(|user| user.id == article.user_id)
+            })
+        {
+            return false;
+        }
+        if !query.tags.is_empty() {
+            let has_tag = |tag: &String| self.tags.contains(&(article.id, tag.clone()));
+            let tagged = match query.tag_match {
+                TagMatch::Any => query.tags.iter().any(has_tag),
+                TagMatch::All => query.tags.iter().all(has_tag),
+            };
+            if !tagged {
+                return false;
+            }
+        }
+        if let Some(favorited) = &query.favorited {
+            match self.user_by_username(favorited) {
+                Some(user) if self.favorites.contains_key(&(user.id, article.id)) => {}
+                _ => return false,
+            }
+        }
+        if query.since.is_some_and(|since| article.created_at < since)
+            || query.until.is_some_and(|until| article.created_at >= until)
+        {
+            return false;
+        }
+        if query.exclude_own && query.viewer_id == Some(article.user_id) {
+            return false;
+        }
+        if let Some(feed_user_id) = query.feed_user_id {
+            if !self.follows.contains(&(feed_user_id, article.user_id)) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Truncated to milliseconds like the `DATETIME(3)` columns.
+fn now() -> NaiveDateTime {
+    Utc::now().naive_utc().trunc_subsecs(3)
+}
+
+fn position(article: &ArticleEntity) -> (NaiveDateTime, i64) {
+    (article.created_at, article.id)
+}
+
+fn cursor_position(cursor: &ArticleCursor) -> (NaiveDateTime, i64) {
+    (cursor.created_at, cursor.id)
+}
+
+#[async_trait]
+impl UserRepo for InMemoryRepository {
+    async fn insert_user(
+        &self,
+        username: String,
+        email: String,
+        password: String,
+    ) -> Result<u64, AppError> {
+        let hash_password = encrypt_password(password);
+
+        let mut state = self.write();
+        state.check_unique_user(None, Some(&username), Some(&email))?;
+        let id = state.next_id();
+        state.users.insert(
+            id,
+            UserEntity {
+                id,
+                username,
+                email,
+                password: hash_password,
+                image: None,
+                bio: None,
+            },
+        );
+        Ok(id as u64)
+    }
+
+    async fn select_user_by_id(&self, id: i64) -> Result<UserEntity, AppError> {
+        self.read()
+            .users
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound("user".to_string()))
+    }
+
+    async fn select_users_by_ids(&self, ids: &[i64]) -> Result<Vec<UserEntity>, AppError> {
+        let state = self.read();
+        Ok(ids
+            .iter()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter_map(|id| state.users.get(id).cloned())
+            .collect())
+    }
+
+    async fn select_user_by_email(&self, email: String) -> Result<UserEntity, AppError> {
+        self.read()
+            .user_by_email(&email)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound("user".to_string()))
+    }
+
+    async fn select_user_by_username(&self, username: String) -> Result<UserEntity, AppError> {
+        self.read()
+            .user_by_username(&username)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound("user".to_string()))
+    }
+
+    async fn update_user_by_id(
+        &self,
+        id: i64,
+        update_form: UserUpdateForm,
+    ) -> Result<(), AppError> {
+        let password = update_form.password.map(encrypt_password);
+
+        let mut state = self.write();
+        state.check_unique_user(
+            Some(id),
+            update_form.username.as_deref(),
+            update_form.email.as_deref(),
+        )?;
+        let user = state
+            .users
+            .get_mut(&id)
+            .ok_or_else(|| AppError::NotFound("user".to_string()))?;
+        if let Some(username) = update_form.username {
+            user.username = username;
+        }
+        if let Some(email) = update_form.email {
+            user.email = email;
+        }
+        if let Some(password) = password {
+            user.password = password;
+        }
+        if let Some(bio) = update_form.bio {
+            user.bio = Some(bio);
+        }
+        if let Some(image) = update_form.image {
+            user.image = Some(image);
+        }
+        Ok(())
+    }
+
+    async fn select_login_failures(&self, email: &str) -> Result<LoginFailures, AppError> {
+        let state = self.read();
+        Ok(state
+            .user_by_email(email)
+            .and_then(|user| state.login_failures.get(&user.id))
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn record_login_failure(&self, email: &str) -> Result<i32, AppError> {
+        let mut state = self.write();
+        let Some(user_id) = state.user_by_email(email).map(|u| u.id) else {
+            return Ok(0);
+        };
+        let failures = state.login_failures.entry(user_id).or_default();
+        failures.failed_login_count += 1;
+        Ok(failures.failed_login_count)
+    }
+
+    async fn lock_user(&self, email: &str, until: Option<NaiveDateTime>) -> Result<(), AppError> {
+        let mut state = self.write();
+        let user_id = state.user_by_email(email).map(|u| u.id);
+        if let (Some(user_id), Some(until)) = (user_id, until) {
+            state
+                .login_failures
+                .entry(user_id)
+                .or_default()
+                .locked_until = Some(until);
+        }
+        Ok(())
+    }
+
+    async fn reset_login_failures(&self, user_id: i64) -> Result<(), AppError> {
+        self.write().login_failures.remove(&user_id);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FollowRepo for InMemoryRepository {
+    async fn select_follow_by_user(
+        &self,
+        follower_user_id: i64,
+        followee_user_id: i64,
+    ) -> Result<bool, AppError> {
+        Ok(self
+            .read()
+            .follows
+            .contains(&(follower_user_id, followee_user_id)))
+    }
+
+    async fn select_following_ids(
+        &self,
+        follower_user_id: i64,
+        user_ids: &[i64],
+    ) -> Result<HashSet<i64>, AppError> {
+        let state = self.read();
+        Ok(user_ids
+            .iter()
+            .copied()
+            .filter(|id| state.follows.contains(&(follower_user_id, *id)))
+            .collect())
+    }
+
+    async fn insert_follow_by_user(
+        &self,
+        user_id: i64,
+        followee_user_id: i64,
+    ) -> Result<i64, AppError> {
+        let mut state = self.write();
+        if !state.follows.insert((user_id, followee_user_id)) {
+            return Err(AppError::Conflict("follow".to_string()));
+        }
+        Ok(state.next_id())
+    }
+
+    async fn delete_follow_by_user(
+        &self,
+        user_id: i64,
+        followee_user_id: i64,
+    ) -> Result<(), AppError> {
+        if self.write().follows.remove(&(user_id, followee_user_id)) {
+            Ok(())
+        } else {
+            Err(AppError::NotFound("follow".to_string()))
+        }
+    }
+}
+
+#[async_trait]
+impl ArticleRepo for InMemoryRepository {
+    async fn insert_article(
+        &self,
+        create_form: ArticleCreateForm,
+        user_id: i64,
+    ) -> Result<u64, AppError> {
+        let base = slug::base_slug(&create_form.title);
+        let created_at = now();
+
+        let mut state = self.write();
+        let slug = slug::unique_slug(&base, &state.taken_slugs(&base, 0));
+        let id = state.next_id();
+        for tag in &create_form.tag_list {
+            state.tags.insert((id, tag.clone()));
+        }
+        state.articles.insert(
+            id,
+            ArticleEntity {
+                id,
+                title: create_form.title,
+                slug,
+                body: create_form.body,
+                description: create_form.description,
+                created_at,
+                updated_at: created_at,
+                tag_list: serde_json::to_string(&create_form.tag_list).unwrap_or("[]".to_string()),
+                user_id,
+                favorites_count: 0,
+            },
+        );
+        state.reindex(id);
+        Ok(id as u64)
+    }
+
+    async fn select_articles_by_query(&self, query: ArticleQuery) -> Result<ArticlePage, AppError> {
+        let window = PageWindow::from_query(&query)?;
+
+        let state = self.read();
+        let mut articles: Vec<&ArticleEntity> = state
+            .articles
+            .values()
+            .filter(|a| state.matches(a, &query))
+            .collect();
+        let articles_count = articles.len() as i64;
+
+        // 先按时间倒序，稳定排序后同分的文章仍是新文章在前
+        articles.sort_by_key(|a| Reverse(position(a)));
+        match window.sort {
+            ArticleSort::Latest | ArticleSort::Oldest => {
+                if window.order() == "asc" {
+                    articles.reverse();
+                }
+            }
+            sort => {
+                let cutoffs = trending_cutoffs();
+                articles.sort_by_cached_key(|a| Reverse(state.sort_score(a.id, sort, &cutoffs)));
+            }
+        }
+        let articles = articles
+            .into_iter()
+            .filter(|a| match window.keyset() {
+                Some((cursor, "<")) => position(a) < cursor_position(cursor),
+                Some((cursor, _)) => position(a) > cursor_position(cursor),
+                None => true,
+            })
+            .skip(window.offset as usize)
+            .take(window.fetch_limit() as usize)
+            .map(|a| state.article(a))
+            .collect();
+
+        Ok(window.finish(articles, articles_count))
+    }
+
+    async fn search_articles(
+        &self,
+        terms: &[String],
+        query: ArticleQuery,
+    ) -> Result<ArticlePage, AppError> {
+        let window = PageWindow::from_query(&query)?;
+
+        let state = self.read();
+        let mut articles: Vec<(&ArticleEntity, f64)> = state
+            .search_index
+            .search(terms)
+            .into_iter()
+            .filter_map(|(id, score)| state.articles.get(&id).map(|a| (a, score)))
+            .filter(|(a, _)| state.matches(a, &query))
+            .collect();
+        let articles_count = articles.len() as i64;
+
+        // 相关度相同时新文章在前，与数据库后端的 id desc 一致
+        articles.sort_by(|(a, x), (b, y)| y.total_cmp(x).then(b.id.cmp(&a.id)));
+        let articles = articles
+            .into_iter()
+            .skip(window.offset as usize)
+            .take(window.limit)
+            .map(|(a, _)| state.article(a))
+            .collect();
+
+        Ok(PageWindow::finish_ranked(articles, articles_count))
+    }
+
+    async fn select_article_by_id(&self, id: u64) -> Result<ArticleEntity, AppError> {
+        let state = self.read();
+        state
+            .articles
+            .get(&(id as i64))
+            .map(|a| state.article(a))
+            .ok_or_else(|| AppError::NotFound("article".to_string()))
+    }
+
+    async fn select_article_by_slug(&self, slug: String) -> Result<ArticleEntity, AppError> {
+        let state = self.read();
+        state
+            .article_by_slug(&slug)
+            .map(|a| state.article(a))
+            .ok_or_else(|| AppError::NotFound("article".to_string()))
+    }
+
+    async fn select_current_slug(&self, old_slug: String) -> Result<Option<String>, AppError> {
+        let state = self.read();
+        Ok(state
+            .slug_history
+            .get(&old_slug)
+            .and_then(|id| state.articles.get(id))
+            .map(|a| a.slug.clone()))
+    }
+
+    async fn update_article_by_slug(
+        &self,
+        user_id: i64,
+        slug: String,
+        update_form: ArticleUpdateForm,
+    ) -> Result<(), AppError> {
+        let mut state = self.write();
+        let (id, old_title) = state
+            .article_by_slug(&slug)
+            .filter(|a| a.user_id == user_id)
+            .map(|a| (a.id, a.title.clone()))
+            .ok_or_else(|| AppError::NotFound("article".to_string()))?;
+
+        let new_slug = match &update_form.title {
+            Some(title) => {
+                let base = slug::base_slug(title);
+                if slug::keeps_slug(&slug, &old_title, title) {
+                    None
+                } else {
+                    Some(slug::unique_slug(&base, &state.taken_slugs(&base, id)))
+                }
+            }
+            None => None,
+        };
+        if let Some(new_slug) = &new_slug {
+            state.slug_history.remove(new_slug);
+            state.slug_history.insert(slug, id);
+        }
+
+        let article = state.articles.get_mut(&id).expect("article exists");
+        article.updated_at = now();
+        if let Some(new_slug) = new_slug {
+            article.slug = new_slug;
+        }
+        if let Some(title) = update_form.title {
+            article.title = title;
+        }
+        if let Some(body) = update_form.body {
+            article.body = body;
+        }
+        if let Some(description) = update_form.description {
+            article.description = description;
+        }
+        if let Some(tag_list) = update_form.tag_list {
+            article.tag_list = serde_json::to_string(&tag_list).unwrap_or("[]".to_string());
+            state.tags.retain(|(article_id, _)| *article_id != id);
+            state.tags.extend(tag_list.into_iter().map(|tag| (id, tag)));
+        }
+        state.reindex(id);
+        Ok(())
+    }
+
+    async fn delete_article_by_slug(&self, user_id: i64, slug: String) -> Result<(), AppError> {
+        let mut state = self.write();
+        let id = state
+            .article_by_slug(&slug)
+            .filter(|a| a.user_id == user_id)
+            .map(|a| a.id)
+            .ok_or_else(|| AppError::NotFound("article".to_string()))?;
+        state.articles.remove(&id);
+        state.reindex(id);
+        state.tags.retain(|(article_id, _)| *article_id != id);
+        state
+            .favorites
+            .retain(|(_, article_id), _| *article_id != id);
+        state.comments.retain(|_, c| c.article_id != id);
+        state.slug_history.retain(|_, article_id| *article_id != id);
+        Ok(())
+    }
+
+    async fn insert_article_favorite(
+        &self,
+        user_id: i64,
+        article_id: i64,
+    ) -> Result<i64, AppError> {
+        let mut state = self.write();
+        if state.favorites.contains_key(&(user_id, article_id)) {
+            return Err(AppError::Conflict("favorite".to_string()));
+        }
+        state.favorites.insert((user_id, article_id), now());
+        Ok(state.next_id())
+    }
+
+    async fn select_article_favorite(
+        &self,
+        user_id: Option<i64>,
+        article_id: i64,
+    ) -> Result<bool, AppError> {
+        Ok(match user_id {
+            Some(user_id) => self.read().favorites.contains_key(&(user_id, article_id)),
+            None => false,
+        })
+    }
+
+    async fn select_favorited_article_ids(
+        &self,
+        user_id: i64,
+        article_ids: &[i64],
+    ) -> Result<HashSet<i64>, AppError> {
+        let state = self.read();
+        Ok(article_ids
+            .iter()
+            .copied()
+            .filter(|id| state.favorites.contains_key(&(user_id, *id)))
+            .collect())
+    }
+
+    async fn delete_article_favorite(&self, user_id: i64, article_id: i64) -> Result<(), AppError> {
+        if self
+            .write()
+            .favorites
+            .remove(&(user_id, article_id))
+            .is_some()
+        {
+            Ok(())
+        } else {
+            Err(AppError::NotFound("favorite".to_string()))
+        }
+    }
+}
+
+#[async_trait]
+impl CommentRepo for InMemoryRepository {
+    async fn select_comments_by_article_id(
+        &self,
+        article_id: i64,
+    ) -> Result<Vec<CommentEntity>, AppError> {
+        Ok(self
+            .read()
+            .comments
+            .values()
+            .rev()
+            .filter(|c| c.article_id == article_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_comment_by_id(&self, comment_id: i64) -> Result<CommentEntity, AppError> {
+        self.read()
+            .comments
+            .get(&comment_id)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound("comment".to_string()))
+    }
+
+    async fn insert_article_comment(
+        &self,
+        user_id: i64,
+        body: String,
+        article_id: i64,
+    ) -> Result<i64, AppError> {
+        let created_at = now();
+        let mut state = self.write();
+        let id = state.next_id();
+        state.comments.insert(
+            id,
+            CommentEntity {
+                id,
+                body,
+                created_at,
+                updated_at: created_at,
+                article_id,
+                user_id,
+            },
+        );
+        Ok(id)
+    }
+
+    async fn delete_comment_by_id(&self, comment_id: i64) -> Result<(), AppError> {
+        match self.write().comments.remove(&comment_id) {
+            Some(_) => Ok(()),
+            None => Err(AppError::NotFound("comment".to_string())),
+        }
+    }
+}
+
+#[async_trait]
+impl TagRepo for InMemoryRepository {
+    async fn select_popular_tags(&self, query: TagQuery) -> Result<Vec<String>, AppError> {
+        let prefix = query.prefix.unwrap_or_default();
+        let limit = query
+            .limit
+            .map_or(usize::MAX, |limit| limit.max(0) as usize);
+        Ok(self
+            .read()
+            .popular_tags(|name| name.starts_with(&prefix), limit))
+    }
+
+    async fn select_tags_by_prefix(
+        &self,
+        prefix: &str,
+        limit: i64,
+    ) -> Result<Vec<String>, AppError> {
+        let prefix = prefix.to_lowercase();
+        Ok(self.read().popular_tags(
+            |name| name.to_lowercase().starts_with(&prefix),
+            limit.max(0) as usize,
+        ))
+    }
+}
+
+#[async_trait]
+impl SessionRepo for InMemoryRepository {
+    async fn insert_session(
+        &self,
+        user_id: i64,
+        refresh_token_hash: String,
+        device: SessionDevice,
+        expires_at: NaiveDateTime,
+    ) -> Result<i64, AppError> {
+        let created_at = now();
+        let mut state = self.write();
+        let id = state.next_id();
+        state.sessions.insert(
+            id,
+            SessionEntity {
+                id,
+                created_at,
+                updated_at: created_at,
+                user_id,
+                refresh_token_hash,
+                user_agent: device.user_agent,
+                ip: device.ip,
+                expires_at,
+                revoked_at: None,
+            },
+        );
+        Ok(id)
+    }
+
+    async fn select_session_by_id(&self, id: i64) -> Result<SessionEntity, AppError> {
+        self.read()
+            .sessions
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound("session".to_string()))
+    }
+
+    async fn select_session_by_refresh_token(
+        &self,
+        refresh_token_hash: &str,
+    ) -> Result<SessionEntity, AppError> {
+        self.read()
+            .sessions
+            .values()
+            .find(|s| s.refresh_token_hash == refresh_token_hash)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound("session".to_string()))
+    }
+
+    async fn rotate_session(
+        &self,
+        id: i64,
+        old_hash: &str,
+        new_hash: String,
+        expires_at: NaiveDateTime,
+    ) -> Result<(), AppError> {
+        match self.write().sessions.get_mut(&id) {
+            Some(session)
+                if session.refresh_token_hash == old_hash && session.revoked_at.is_none() =>
+            {
+                session.refresh_token_hash = new_hash;
+                session.expires_at = expires_at;
+                session.updated_at = now();
+                Ok(())
+            }
+            _ => Err(AppError::NotFound("session".to_string())),
+        }
+    }
+
+    async fn revoke_session(&self, user_id: i64, id: i64) -> Result<(), AppError> {
+        match self.write().sessions.get_mut(&id) {
+            Some(session) if session.user_id == user_id && session.revoked_at.is_none() => {
+                session.revoked_at = Some(now());
+                Ok(())
+            }
+            _ => Err(AppError::NotFound("session".to_string())),
+        }
+    }
+
+    async fn revoke_user_sessions(&self, user_id: i64) -> Result<u64, AppError> {
+        let revoked_at = now();
+        let mut revoked = 0;
+        for session in self.write().sessions.values_mut() {
+            if session.user_id == user_id && session.revoked_at.is_none() {
+                session.revoked_at = Some(revoked_at);
+                revoked += 1;
+            }
+        }
+        Ok(revoked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+
+    use super::*;
+
+    async fn article(repo: &InMemoryRepository, user_id: i64, title: &str) -> i64 {
+        let form = ArticleCreateForm {
+            title: title.to_string(),
+            description: "A description".to_string(),
+            body: "A body".to_string(),
+            tag_list: Vec::new(),
+        };
+        repo.insert_article(form, user_id).await.unwrap() as i64
+    }
+
+    async fn titles(repo: &InMemoryRepository, sort: &str) -> Vec<String> {
+        let query = ArticleQuery {
+            sort: Some(sort.to_string()),
+            ..ArticleQuery::default()
+        };
+        let page = repo.select_articles_by_query(query).await.unwrap();
+        page.articles.into_iter().map(|a| a.title).collect()
+    }
+
+    #[actix_web::test]
+    async fn trending_weighs_favorites_by_age() {
+        let repo = InMemoryRepository::new();
+        let mut users = Vec::new();
+        for name in ["alice", "bob", "carol"] {
+            let id = repo
+                .insert_user(
+                    name.to_string(),
+                    format!("{}@example.com", name),
+                    String::new(),
+                )
+                .await
+                .unwrap();
+            users.push(id as i64);
+        }
+        let old = article(&repo, users[0], "Old favorite").await;
+        let new = article(&repo, users[0], "New favorite").await;
+        article(&repo, users[0], "Quiet").await;
+        for &user in &users[..2] {
+            repo.insert_article_favorite(user, old).await.unwrap();
+        }
+        repo.insert_article_favorite(users[2], new).await.unwrap();
+
+        // 两个三周前的收藏只落在 30 天窗口里，各得 1 分；今天的收藏得 3 分
+        let weeks_ago = now() - TimeDelta::try_weeks(3).unwrap();
+        for favorited_at in repo
+            .write()
+            .favorites
+            .iter_mut()
+            .filter(|((_, article_id), _)| *article_id == old)
+            .map(|(_, favorited_at)| favorited_at)
+        {
+            *favorited_at = weeks_ago;
+        }
+
+        assert_eq!(
+            titles(&repo, "most_favorited").await,
+            ["Old favorite", "New favorite", "Quiet"]
+        );
+        assert_eq!(
+            titles(&repo, "trending").await,
+            ["New favorite", "Old favorite", "Quiet"]
+        );
+
+        // 超过 30 天的收藏不再计分，同分时新文章在前
+        let long_ago = now() - TimeDelta::try_days(60).unwrap();
+        for favorited_at in repo.write().favorites.values_mut() {
+            *favorited_at = long_ago;
+        }
+        assert_eq!(
+            titles(&repo, "trending").await,
+            ["Quiet", "New favorite", "Old favorite"]
+        );
+    }
+}
@@ -1,50 +1,630 @@
+use std::collections::HashSet;
+use std::sync::Arc;
 
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, TimeDelta, Utc};
 
-use actix_web::{body::BoxBody, http::StatusCode, HttpResponse};
-use derive_more::{Display, Error, From};
-use realworld_rust_actix_web::ServiceError;
-
-pub mod article;
-pub mod tag;
-pub mod user;
-pub mod comment;
-
-#[derive(Debug, Display, Error, From)]
-pub enum PersistenceError {
-    // EmptyBankName,
-    // EmptyCountry,
-    // EmptyBranch,
-    // EmptyLocation,
-    // EmptyTellerName,
-    // EmptyCustomerName,
-    MysqlError(sqlx::Error),
-
-    Unknown,
-}
-
-impl actix_web::ResponseError for PersistenceError {
-    fn error_response(&self) -> HttpResponse<BoxBody> {
-        match self {
-            // PersistenceError::EmptyBankName
-            // | PersistenceError::EmptyCountry
-            // | PersistenceError::EmptyBranch
-            // | PersistenceError::EmptyLocation
-            // | PersistenceError::EmptyTellerName
-            // | PersistenceError::EmptyCustomerName => StatusCode::BAD_REQUEST,
-            PersistenceError::MysqlError(e) => {
-                log::error!("PersistenceError {}", e);
-
-                HttpResponse::with_body(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    serde_json::to_string(&ServiceError::new(e.to_string())).unwrap(),
-                )
-                .map_into_boxed_body()
-            }
-            PersistenceError::Unknown => HttpResponse::with_body(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                serde_json::to_string(&ServiceError::new("unknown error".to_string())).unwrap(),
+use crate::config::DatabaseConfig;
+use crate::error::AppError;
+use crate::models::article::{
+    ArticleCreateForm, ArticleCursor, ArticleEntity, ArticlePage, ArticleQuery, ArticleSort,
+    ArticleUpdateForm,
+};
+use crate::models::comment::CommentEntity;
+use crate::models::session::{SessionDevice, SessionEntity};
+use crate::models::user::{LoginFailures, UserEntity, UserUpdateForm};
+use crate::models::TagQuery;
+
+pub mod memory;
+#[cfg(feature = "mysql")]
+pub mod mysql;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+#[async_trait]
+pub trait UserRepo: Send + Sync {
+    async fn insert_user(
+        &self,
+        username: String,
+        email: String,
+        password: String,
+    ) -> Result<u64, AppError>;
+
+    async fn select_user_by_id(&self, id: i64) -> Result<UserEntity, AppError>;
+
+    /// Loads all users in `ids` with a single query.
+    async fn select_users_by_ids(&self, ids: &[i64]) -> Result<Vec<UserEntity>, AppError>;
+
+    async fn select_user_by_email(&self, email: String) -> Result<UserEntity, AppError>;
+
+    async fn select_user_by_username(&self, username: String) -> Result<UserEntity, AppError>;
+
+    async fn update_user_by_id(&self, id: i64, update_form: UserUpdateForm)
+        -> Result<(), AppError>;
+
+    /// The failed logins of the user with `email`, or none when no user has
+    /// it. The login methods take the email rather than a user id so that
+    /// unknown emails run the same statements as registered ones.
+    async fn select_login_failures(&self, email: &str) -> Result<LoginFailures, AppError>;
+
+    /// Counts a failed login and returns how many failed in a row, 0 when no
+    /// user has `email`.
+    async fn record_login_failure(&self, email: &str) -> Result<i32, AppError>;
+
+    /// Refuses logins to the user with `email` until `until`; `None` leaves
+    /// any lock as it is.
+    async fn lock_user(&self, email: &str, until: Option<NaiveDateTime>) -> Result<(), AppError>;
+
+    /// Clears the failure count and any lock after a successful login.
+    async fn reset_login_failures(&self, user_id: i64) -> Result<(), AppError>;
+}
+
+#[async_trait]
+pub trait FollowRepo: Send + Sync {
+    async fn select_follow_by_user(
+        &self,
+        follower_user_id: i64,
+        followee_user_id: i64,
+    ) -> Result<bool, AppError>;
+
+    /// Anonymous viewers follow nobody.
+    async fn is_following(&self, viewer_id: Option<i64>, user_id: i64) -> Result<bool, AppError> {
+        match viewer_id {
+            Some(viewer_id) => self.select_follow_by_user(viewer_id, user_id).await,
+            None => Ok(false),
+        }
+    }
+
+    /// Returns which of `user_ids` the follower follows, with a single query.
+    async fn select_following_ids(
+        &self,
+        follower_user_id: i64,
+        user_ids: &[i64],
+    ) -> Result<HashSet<i64>, AppError>;
+
+    async fn insert_follow_by_user(
+        &self,
+        user_id: i64,
+        followee_user_id: i64,
+    ) -> Result<i64, AppError>;
+
+    async fn delete_follow_by_user(
+        &self,
+        user_id: i64,
+        followee_user_id: i64,
+    ) -> Result<(), AppError>;
+}
+
+#[async_trait]
+pub trait ArticleRepo: Send + Sync {
+    /// Inserts the article and its tags in one transaction. The slug is derived
+    /// from the title, with a numeric suffix if it is already taken.
+    async fn insert_article(
+        &self,
+        create_form: ArticleCreateForm,
+        user_id: i64,
+    ) -> Result<u64, AppError>;
+
+    /// Returns one page of articles together with the total number of articles
+    /// matching the same filters, ignoring `limit`/`offset` and cursors.
+    ///
+    /// Articles are ordered by `query.sort`, one of the `ArticleSort` names:
+    /// `latest` (the default, `created_at desc, id desc`), `oldest`,
+    /// `most_favorited`, `most_commented` or `trending`. Popularity orders
+    /// break ties newest first. Any other value is rejected with 422.
+    ///
+    /// For `latest` and `oldest`, `after`/`before` select the page by keyset
+    /// on `(created_at, id)` instead of `offset`, so rows published while a
+    /// client scrolls never shift the page boundaries. Popularity scores
+    /// change between requests and have no keyset, so those orders page by
+    /// `offset` only: cursors are rejected and none are returned.
+    async fn select_articles_by_query(&self, query: ArticleQuery) -> Result<ArticlePage, AppError>;
+
+    /// Articles matching any of `terms` (see `search::terms`) in title,
+    /// description or body, most relevant first. Filters and `limit`/`offset`
+    /// come from `query`; cursors are ignored and none are returned.
+    async fn search_articles(
+        &self,
+        terms: &[String],
+        query: ArticleQuery,
+    ) -> Result<ArticlePage, AppError>;
+
+    async fn select_article_by_id(&self, id: u64) -> Result<ArticleEntity, AppError>;
+
+    async fn select_article_by_slug(&self, slug: String) -> Result<ArticleEntity, AppError>;
+
+    /// The current slug of the article that was published under `old_slug`
+    /// before a title change, if any.
+    async fn select_current_slug(&self, old_slug: String) -> Result<Option<String>, AppError>;
+
+    /// Applies the fields present in `update_form` and bumps `updated_at`.
+    /// A `tag_list` replaces the article's tags, rows and JSON column alike.
+    /// When a new title changes the slug, the old one is kept in the slug
+    /// history.
+    async fn update_article_by_slug(
+        &self,
+        user_id: i64,
+        slug: String,
+        update_form: ArticleUpdateForm,
+    ) -> Result<(), AppError>;
+
+    /// Deletes the article with its tags, favorites and comments in one
+    /// transaction.
+    async fn delete_article_by_slug(&self, user_id: i64, slug: String) -> Result<(), AppError>;
+
+    async fn insert_article_favorite(&self, user_id: i64, article_id: i64)
+        -> Result<i64, AppError>;
+
+    async fn select_article_favorite(
+        &self,
+        user_id: Option<i64>,
+        article_id: i64,
+    ) -> Result<bool, AppError>;
+
+    /// Returns which of `article_ids` the user has favorited, with a single query.
+    async fn select_favorited_article_ids(
+        &self,
+        user_id: i64,
+        article_ids: &[i64],
+    ) -> Result<HashSet<i64>, AppError>;
+
+    async fn delete_article_favorite(&self, user_id: i64, article_id: i64) -> Result<(), AppError>;
+}
+
+#[async_trait]
+pub trait CommentRepo: Send + Sync {
+    async fn select_comments_by_article_id(
+        &self,
+        article_id: i64,
+    ) -> Result<Vec<CommentEntity>, AppError>;
+
+    async fn get_comment_by_id(&self, comment_id: i64) -> Result<CommentEntity, AppError>;
+
+    async fn insert_article_comment(
+        &self,
+        user_id: i64,
+        body: String,
+        article_id: i64,
+    ) -> Result<i64, AppError>;
+
+    async fn delete_comment_by_id(&self, comment_id: i64) -> Result<(), AppError>;
+}
+
+#[async_trait]
+pub trait TagRepo: Send + Sync {
+    /// Names of tags used by at least one article, most used first and ties
+    /// by name, narrowed by `query.prefix` and capped at `query.limit`.
+    async fn select_popular_tags(&self, query: TagQuery) -> Result<Vec<String>, AppError>;
+
+    /// Up to `limit` tags whose name starts with `prefix` ignoring case,
+    /// ranked like `select_popular_tags`. Each backend indexes the case-folded
+    /// name so the prefix match does not scan `tags`.
+    async fn select_tags_by_prefix(
+        &self,
+        prefix: &str,
+        limit: i64,
+    ) -> Result<Vec<String>, AppError>;
+}
+
+#[async_trait]
+pub trait SessionRepo: Send + Sync {
+    /// Opens a session and returns its id.
+    async fn insert_session(
+        &self,
+        user_id: i64,
+        refresh_token_hash: String,
+        device: SessionDevice,
+        expires_at: NaiveDateTime,
+    ) -> Result<i64, AppError>;
+
+    async fn select_session_by_id(&self, id: i64) -> Result<SessionEntity, AppError>;
+
+    async fn select_session_by_refresh_token(
+        &self,
+        refresh_token_hash: &str,
+    ) -> Result<SessionEntity, AppError>;
+
+    /// Replaces the refresh token of an unrevoked session and extends it.
+    /// `NotFound` unless `old_hash` is still current, so each refresh token
+    /// works once even when two refreshes race.
+    async fn rotate_session(
+        &self,
+        id: i64,
+        old_hash: &str,
+        new_hash: String,
+        expires_at: NaiveDateTime,
+    ) -> Result<(), AppError>;
+
+    /// Revokes one active session of `user_id`.
+    async fn revoke_session(&self, user_id: i64, id: i64) -> Result<(), AppError>;
+
+    /// Revokes every active session of `user_id` and returns how many.
+    async fn revoke_user_sessions(&self, user_id: i64) -> Result<u64, AppError>;
+}
+
+/// Everything the routes need from a storage backend. Handlers take
+/// `web::Data<dyn Repository>`.
+pub trait Repository:
+    UserRepo + FollowRepo + ArticleRepo + CommentRepo + TagRepo + SessionRepo
+{
+}
+
+impl<T> Repository for T where
+    T: UserRepo + FollowRepo + ArticleRepo + CommentRepo + TagRepo + SessionRepo
+{
+}
+
+/// Opens the backend named by the scheme of `config.url` (`mysql://..`,
+/// `postgres://..`, `sqlite://..`, `sqlite::memory:`). Each backend is behind
+/// the cargo feature of the same name.
+pub async fn connect(
+    config: &DatabaseConfig,
+    // 不启用任何数据库后端时（只用内存后端测试）用不到
+    #[cfg_attr(
+        not(any(feature = "mysql", feature = "postgres", feature = "sqlite")),
+        allow(unused_variables)
+    )]
+    migrate: bool,
+) -> Result<Arc<dyn Repository>, sqlx::Error> {
+    let scheme = config.url.split(':').next().unwrap_or_default();
+    match scheme {
+        #[cfg(feature = "mysql")]
+        "mysql" => Ok(Arc::new(
+            mysql::MySqlRepository::connect(config, migrate).await?,
+        )),
+        #[cfg(feature = "postgres")]
+        "postgres" | "postgresql" => Ok(Arc::new(
+            postgres::PostgresRepository::connect(config, migrate).await?,
+        )),
+        #[cfg(feature = "sqlite")]
+        "sqlite" => Ok(Arc::new(
+            sqlite::SqliteRepository::connect(config, migrate).await?,
+        )),
+        _ => Err(sqlx::Error::Configuration(
+            format!(
+                "unsupported database url scheme {:?}, enabled backends: {}",
+                scheme,
+                enabled_backends().join(", ")
             )
-            .map_into_boxed_body(),
+            .into(),
+        )),
+    }
+}
+
+fn enabled_backends() -> Vec<&'static str> {
+    [
+        (cfg!(feature = "mysql"), "mysql"),
+        (cfg!(feature = "postgres"), "postgres"),
+        (cfg!(feature = "sqlite"), "sqlite"),
+    ]
+    .into_iter()
+    .filter_map(|(enabled, name)| enabled.then_some(name))
+    .collect()
+}
+
+/// Page size when the query gives no `limit`.
+const DEFAULT_PAGE_SIZE: i32 = 20;
+/// Larger `limit`s are cut down to this, so no request loads the whole table.
+const MAX_PAGE_SIZE: i32 = 100;
+
+/// The slice of an article listing requested by `ArticleQuery`, shared by the
+/// backends so offset and keyset paging behave identically.
+///
+/// Backends fetch up to `fetch_limit()` rows past the cursor (or `offset`) in
+/// `sort` order and hand them to `finish`. For the chronological sorts that is
+/// `created_at {order()}, id {order()}`, with the cursor compared by
+/// `keyset()`.
+#[derive(Debug)]
+pub struct PageWindow {
+    pub sort: ArticleSort,
+    pub after: Option<ArticleCursor>,
+    pub before: Option<ArticleCursor>,
+    pub limit: usize,
+    pub offset: i64,
+}
+
+impl PageWindow {
+    pub fn from_query(query: &ArticleQuery) -> Result<Self, AppError> {
+        let sort = match query.sort.as_deref().filter(|sort| !sort.is_empty()) {
+            Some(sort) => sort.parse()?,
+            None => ArticleSort::default(),
+        };
+        let after = decode_cursor(&query.after)?;
+        let before = decode_cursor(&query.before)?;
+        if after.is_some() && before.is_some() {
+            return Err(AppError::validation(
+                "cursor",
+                "after and before can't be combined",
+            ));
+        }
+        let cursor_mode = after.is_some() || before.is_some();
+        if cursor_mode && !sort.supports_cursor() {
+            return Err(AppError::validation(
+                "cursor",
+                "can only be used with sort=latest or sort=oldest",
+            ));
         }
+        Ok(PageWindow {
+            sort,
+            after,
+            before,
+            limit: query
+                .limit
+                .unwrap_or(DEFAULT_PAGE_SIZE)
+                .clamp(0, MAX_PAGE_SIZE) as usize,
+            offset: if cursor_mode {
+                0
+            } else {
+                query.offset.unwrap_or(0).max(0) as i64
+            },
+        })
+    }
+
+    /// Direction of the chronological sorts: `desc` for `latest` and `asc`
+    /// for `oldest`, reversed when paging backwards from a `before` cursor.
+    pub fn order(&self) -> &'static str {
+        let ascending = self.sort == ArticleSort::Oldest;
+        if ascending != self.before.is_some() {
+            "asc"
+        } else {
+            "desc"
+        }
+    }
+
+    /// The cursor, if any, and how `(created_at, id)` must compare to it.
+    pub fn keyset(&self) -> Option<(&ArticleCursor, &'static str)> {
+        let operator = if self.order() == "asc" { ">" } else { "<" };
+        self.after
+            .as_ref()
+            .or(self.before.as_ref())
+            .map(|cursor| (cursor, operator))
+    }
+
+    /// One extra row tells whether another page exists.
+    pub fn fetch_limit(&self) -> i64 {
+        self.limit as i64 + 1
+    }
+
+    pub fn finish(&self, mut articles: Vec<ArticleEntity>, articles_count: i64) -> ArticlePage {
+        let has_more = articles.len() > self.limit;
+        articles.truncate(self.limit);
+        if self.before.is_some() {
+            articles.reverse();
+        }
+
+        // 后面的文章：after/offset 模式看是否多取到一条；before 模式下游标本身就在后面
+        let later_exists = self.before.is_some() || has_more;
+        // 前面的文章：before 模式看是否多取到一条；after 模式下游标本身就在前面
+        let earlier_exists = if self.before.is_some() {
+            has_more
+        } else {
+            self.after.is_some() || self.offset > 0
+        };
+        // 其它排序没有可编码的位置，只能用 offset 翻页
+        let cursors = self.sort.supports_cursor();
+        let next_cursor = articles
+            .last()
+            .filter(|_| cursors && later_exists)
+            .map(|a| ArticleCursor::of(a).encode());
+        let prev_cursor = articles
+            .first()
+            .filter(|_| cursors && earlier_exists)
+            .map(|a| ArticleCursor::of(a).encode());
+
+        ArticlePage {
+            articles,
+            articles_count,
+            next_cursor,
+            prev_cursor,
+        }
+    }
+
+    /// A page of search results. Relevance order has no position a cursor
+    /// could encode, so backends fetch `limit` rows at `offset` and get no
+    /// cursors back.
+    pub fn finish_ranked(articles: Vec<ArticleEntity>, articles_count: i64) -> ArticlePage {
+        ArticlePage {
+            articles,
+            articles_count,
+            next_cursor: None,
+            prev_cursor: None,
+        }
+    }
+}
+
+/// Ages in days behind the `trending` sort: a favorite scores once for every
+/// window it falls in, so one given today outweighs one from last month.
+const TRENDING_WINDOW_DAYS: [i64; 3] = [1, 7, 30];
+
+/// Start of each trending window, as bound into the `trending` score.
+fn trending_cutoffs() -> [NaiveDateTime; 3] {
+    let now = Utc::now().naive_utc();
+    TRENDING_WINDOW_DAYS
+        .map(|days| now - TimeDelta::try_days(days).expect("trending windows are short"))
+}
+
+/// `LIKE` pattern matching names that start with `prefix`. `!` is the escape
+/// character (`like ? escape '!'`) so the same SQL works on every backend.
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+fn prefix_pattern(prefix: &str) -> String {
+    let mut pattern = String::with_capacity(prefix.len() + 1);
+    for c in prefix.chars() {
+        if matches!(c, '!' | '%' | '_') {
+            pattern.push('!');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+/// An empty cursor is treated as absent.
+fn decode_cursor(cursor: &Option<String>) -> Result<Option<ArticleCursor>, AppError> {
+    match cursor.as_deref() {
+        Some(cursor) if !cursor.is_empty() => Ok(Some(ArticleCursor::decode(cursor)?)),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn article(id: i64) -> ArticleEntity {
+        // id 越大越新
+        let created_at = NaiveDate::from_ymd_opt(2024, 4, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            + TimeDelta::try_minutes(id).unwrap();
+        ArticleEntity {
+            id,
+            title: format!("Article {}", id),
+            slug: format!("article-{}", id),
+            body: String::new(),
+            description: String::new(),
+            created_at,
+            updated_at: created_at,
+            tag_list: "[]".to_string(),
+            user_id: 1,
+            favorites_count: 0,
+        }
+    }
+
+    /// The rows a backend would fetch for `window` out of articles 1..=10.
+    fn fetch(window: &PageWindow) -> ArticlePage {
+        let mut articles: Vec<ArticleEntity> = (1..=10).map(article).collect();
+        if window.order() == "desc" {
+            articles.reverse();
+        }
+        let articles = articles
+            .into_iter()
+            .filter(|a| match window.keyset() {
+                Some((cursor, ">")) => (a.created_at, a.id) > (cursor.created_at, cursor.id),
+                Some((cursor, _)) => (a.created_at, a.id) < (cursor.created_at, cursor.id),
+                None => true,
+            })
+            .skip(window.offset as usize)
+            .take(window.fetch_limit() as usize)
+            .collect();
+        window.finish(articles, 10)
+    }
+
+    fn window_for(pairs: &[(&str, &str)]) -> Result<PageWindow, AppError> {
+        let pairs = pairs
+            .iter()
+            .map(|&(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        PageWindow::from_query(&ArticleQuery::from_pairs(pairs)?)
+    }
+
+    fn cursor(id: i64) -> Option<String> {
+        Some(ArticleCursor::of(&article(id)).encode())
+    }
+
+    fn ids(page: &ArticlePage) -> Vec<i64> {
+        page.articles.iter().map(|a| a.id).collect()
+    }
+
+    #[test]
+    fn limit_defaults_to_20_and_is_capped_at_100() {
+        assert_eq!(window_for(&[]).unwrap().limit, 20);
+        assert_eq!(window_for(&[("limit", "5")]).unwrap().limit, 5);
+        assert_eq!(window_for(&[("limit", "1000")]).unwrap().limit, 100);
+        assert_eq!(window_for(&[("limit", "-1")]).unwrap().limit, 0);
+    }
+
+    #[test]
+    fn cursors_replace_the_offset() {
+        let after = ArticleCursor::of(&article(5)).encode();
+        let window = window_for(&[("after", &after), ("offset", "3")]).unwrap();
+        assert_eq!(window.offset, 0);
+        assert!(window_for(&[("after", &after), ("before", &after)]).is_err());
+        assert!(window_for(&[("after", "garbage")]).is_err());
+    }
+
+    #[test]
+    fn cursors_are_rejected_for_popularity_sorts() {
+        let after = ArticleCursor::of(&article(5)).encode();
+        for sort in ["most_favorited", "most_commented", "trending"] {
+            for key in ["after", "before"] {
+                assert!(
+                    matches!(
+                        window_for(&[("sort", sort), (key, &after)]),
+                        Err(AppError::Validation(errors)) if errors.contains_key("cursor")
+                    ),
+                    "{} with {}",
+                    sort,
+                    key
+                );
+            }
+            assert!(window_for(&[("sort", sort), ("offset", "20")]).is_ok());
+        }
+        assert!(window_for(&[("sort", "oldest"), ("before", &after)]).is_ok());
+    }
+
+    #[test]
+    fn first_page_has_only_a_next_cursor() {
+        let page = fetch(&window_for(&[("limit", "3")]).unwrap());
+        assert_eq!(ids(&page), [10, 9, 8]);
+        assert_eq!(page.articles_count, 10);
+        assert_eq!(page.next_cursor, cursor(8));
+        assert_eq!(page.prev_cursor, None);
+    }
+
+    #[test]
+    fn after_pages_forward_to_the_last_page() {
+        let after = ArticleCursor::of(&article(8)).encode();
+        let page = fetch(&window_for(&[("limit", "3"), ("after", &after)]).unwrap());
+        assert_eq!(ids(&page), [7, 6, 5]);
+        assert_eq!(page.prev_cursor, cursor(7));
+        assert_eq!(page.next_cursor, cursor(5));
+
+        let after = ArticleCursor::of(&article(2)).encode();
+        let page = fetch(&window_for(&[("limit", "3"), ("after", &after)]).unwrap());
+        assert_eq!(ids(&page), [1]);
+        assert_eq!(page.next_cursor, None);
+        assert!(page.prev_cursor.is_some());
+    }
+
+    #[test]
+    fn before_pages_back_in_display_order() {
+        let before = ArticleCursor::of(&article(5)).encode();
+        let page = fetch(&window_for(&[("limit", "3"), ("before", &before)]).unwrap());
+        assert_eq!(ids(&page), [8, 7, 6]);
+        assert_eq!(page.next_cursor, cursor(6));
+        assert_eq!(page.prev_cursor, cursor(8));
+
+        // 回到第一页时没有更前面的文章
+        let before = ArticleCursor::of(&article(8)).encode();
+        let page = fetch(&window_for(&[("limit", "3"), ("before", &before)]).unwrap());
+        assert_eq!(ids(&page), [10, 9]);
+        assert_eq!(page.prev_cursor, None);
+        assert!(page.next_cursor.is_some());
+    }
+
+    #[test]
+    fn oldest_pages_in_ascending_order() {
+        let page = fetch(&window_for(&[("limit", "3"), ("sort", "oldest")]).unwrap());
+        assert_eq!(ids(&page), [1, 2, 3]);
+        let after = page.next_cursor.clone().unwrap();
+        let window = window_for(&[("limit", "3"), ("sort", "oldest"), ("after", &after)]);
+        let page = fetch(&window.unwrap());
+        assert_eq!(ids(&page), [4, 5, 6]);
+    }
+
+    #[test]
+    fn offset_pages_report_earlier_articles_without_cursors_for_ranked_sorts() {
+        let page = fetch(&window_for(&[("limit", "3"), ("offset", "3")]).unwrap());
+        assert_eq!(ids(&page), [7, 6, 5]);
+        assert!(page.prev_cursor.is_some());
+
+        let ranked = PageWindow::finish_ranked(vec![article(1)], 1);
+        assert_eq!(ranked.next_cursor, None);
+        assert_eq!(ranked.prev_cursor, None);
     }
 }
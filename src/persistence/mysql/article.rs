@@ -0,0 +1,553 @@
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::{MySqlConnection, QueryBuilder};
+
+use crate::error::AppError;
+use crate::models::article::{
+    ArticleCreateForm, ArticleEntity, ArticleFavoriteEntity, ArticlePage, ArticleQuery,
+    ArticleSort, ArticleUpdateForm, TagMatch,
+};
+use crate::persistence::{trending_cutoffs, ArticleRepo, PageWindow};
+
+use crate::slug;
+
+use super::{tag::insert_tag, MySqlRepository};
+
+#[async_trait]
+impl ArticleRepo for MySqlRepository {
+    async fn insert_article(
+        &self,
+        create_form: ArticleCreateForm,
+        user_id: i64,
+    ) -> Result<u64, AppError> {
+        let pool = &self.pool;
+        // let mut conn = pool.get_conn()?;
+        let title = create_form.title;
+
+        // 文章和标签在同一个事务里写入，任何一步失败都整体回滚
+        let mut tx = pool.begin().await?;
+        let base = slug::base_slug(&title);
+        let slug = slug::unique_slug(&base, &taken_slugs(&mut tx, &base, 0).await?);
+        let result = sqlx::query!(
+            "insert into article(title, slug, description, body, created_at, updated_at, tag_list, user_id) values (?, ?, ?, ?, ?, ?, ?, ?)",
+            &title,
+            slug,
+            create_form.description,
+            create_form.body,
+            Utc::now().naive_utc(),
+            Utc::now().naive_utc(),
+            serde_json::to_string(&create_form.tag_list).unwrap_or("[]".to_string()),
+            user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let article_id = result.last_insert_id();
+        if article_id == 0 {
+            return Err(AppError::Internal("insert article failed".to_string()));
+        }
+        for tag in create_form.tag_list {
+            insert_tag(&mut tx, tag, article_id as i64).await?;
+        }
+        tx.commit().await?;
+
+        Ok(article_id)
+    }
+
+    async fn select_articles_by_query(
+        &self,
+        query: ArticleQuery,
+    ) -> Result<ArticlePage, AppError> {
+        let pool = &self.pool;
+        let (filter_sql, filter_values) = article_filters(&query);
+
+        let window = PageWindow::from_query(&query)?;
+
+        let mut sql = "SELECT a.id, a.title, a.slug, a.description, a.body, a.created_at, a.updated_at, a.tag_list, a.user_id, count(af.id) as favorites_count 
+        FROM article a left join article_favorite af on a.id = af.article_id ".to_string();
+        sql.push_str(&filter_sql);
+        let mut values = filter_values.clone();
+
+        let conjunction = if filter_sql.is_empty() { " where " } else { " and " };
+        if let Some((cursor, operator)) = window.keyset() {
+            // 向前翻页时按反方向取离游标最近的一页，再反转
+            sql.push_str(conjunction);
+            sql.push_str(&format!(
+                " (a.created_at {op} ? or (a.created_at = ? and a.id {op} ?)) ",
+                op = operator
+            ));
+            values.push(cursor.created_at_param());
+            values.push(cursor.created_at_param());
+            values.push(cursor.id.to_string());
+        }
+        let (order_sql, order_values) = article_order(&window);
+        sql.push_str(" group by a.id order by ");
+        sql.push_str(&order_sql);
+        sql.push_str(" limit ?, ?");
+        values.extend(order_values);
+
+        values.push(window.offset.to_string());
+        values.push(window.fetch_limit().to_string());
+        let mut query_as = sqlx::query_as(sql.as_str());
+        for v in values {
+            query_as = query_as.bind(v);
+        }
+        let articles: Vec<ArticleEntity> = query_as.fetch_all(pool).await?;
+
+        let count_sql = format!("SELECT count(*) FROM article a {}", filter_sql);
+        let mut count_query = sqlx::query_scalar::<_, i64>(count_sql.as_str());
+        for v in filter_values {
+            count_query = count_query.bind(v);
+        }
+        let articles_count = count_query.fetch_one(pool).await?;
+
+        Ok(window.finish(articles, articles_count))
+    }
+
+    async fn search_articles(
+        &self,
+        terms: &[String],
+        query: ArticleQuery,
+    ) -> Result<ArticlePage, AppError> {
+        let pool = &self.pool;
+        let (filter_sql, filter_values) = article_filters(&query);
+        let window = PageWindow::from_query(&query)?;
+        let against = boolean_query(terms);
+
+        // 三列共用一个 FULLTEXT 索引，相关度不区分字段
+        let mut sql = "SELECT a.id, a.title, a.slug, a.description, a.body, a.created_at, a.updated_at, a.tag_list, a.user_id, count(af.id) as favorites_count
+        FROM article a left join article_favorite af on a.id = af.article_id
+        join (select id, match(title, description, body) against (? in boolean mode) as score from article
+            where match(title, description, body) against (? in boolean mode)) m on m.id = a.id ".to_string();
+        sql.push_str(&filter_sql);
+        sql.push_str(" group by a.id, m.score order by m.score desc, a.id desc limit ?, ?");
+        let mut values = vec![against.clone(), against.clone()];
+        values.extend(filter_values.clone());
+        values.push(window.offset.to_string());
+        values.push(window.limit.to_string());
+        let mut query_as = sqlx::query_as(sql.as_str());
+        for v in values {
+            query_as = query_as.bind(v);
+        }
+        let articles: Vec<ArticleEntity> = query_as.fetch_all(pool).await?;
+
+        let count_sql = format!(
+            "SELECT count(*) FROM article a join (select id from article where match(title, description, body) against (? in boolean mode)) m on m.id = a.id {}",
+            filter_sql
+        );
+        let mut count_query = sqlx::query_scalar::<_, i64>(count_sql.as_str()).bind(against);
+        for v in filter_values {
+            count_query = count_query.bind(v);
+        }
+        let articles_count = count_query.fetch_one(pool).await?;
+
+        Ok(PageWindow::finish_ranked(articles, articles_count))
+    }
+
+    //
+    async fn select_article_by_id(
+        &self,
+        id: u64,
+    ) -> Result<ArticleEntity, AppError> {
+        let pool = &self.pool;
+        // let mut conn = pool.get_conn()?;
+
+        // 使用参数化查询以避免SQL注入风险
+        let result = sqlx::query_as!(ArticleEntity,
+            "SELECT a.id, a.title, a.slug, a.description, a.body, a.created_at, a.updated_at, a.tag_list, a.user_id, count(af.id) as favorites_count
+            FROM article a left join article_favorite af on a.id = af.article_id
+            WHERE a.id = ? group by a.id order by a.id desc limit 1",
+            (id)
+            )
+            .fetch_one(pool)
+            .await;
+        // let tags = sqlx::query_scalar!("SELECT name FROM tag WHERE article_id = ?", (id))
+        //     .fetch_all(pool)
+        //     .await?;
+        match result {
+            Ok(article) => Ok(article),
+            Err(sqlx::Error::RowNotFound) => Err(AppError::NotFound("article".to_string())),
+            Err(e) => {
+                log::error!("select article by id error: {}", e);
+                Err(e.into())
+            }
+        }
+    }
+
+    async fn select_article_by_slug(
+        &self,
+        slug: String,
+    ) -> Result<ArticleEntity, AppError> {
+        let pool = &self.pool;
+        // let mut conn = pool.get_conn()?;
+
+        // 使用参数化查询以避免SQL注入风险
+        let result = sqlx::query_as!(ArticleEntity,
+            "SELECT a.id, a.title, a.slug, a.description, a.body, a.created_at, a.updated_at, a.tag_list, a.user_id, count(af.id) as favorites_count
+            FROM article a left join article_favorite af on a.id = af.article_id
+            WHERE a.slug = ? group by a.id order by a.id desc limit 1",
+            (slug)
+            )
+            .fetch_one(pool)
+            .await;
+        // let tags = sqlx::query_scalar!("SELECT name FROM tag WHERE article_id = ?", (id))
+        //     .fetch_all(pool)
+        //     .await?;
+        match result {
+            Ok(article) => Ok(article),
+            Err(sqlx::Error::RowNotFound) => Err(AppError::NotFound("article".to_string())),
+            Err(e) => {
+                log::error!("select article by slug error: {}", e);
+                Err(e.into())
+            }
+        }
+    }
+
+    async fn select_current_slug(&self, old_slug: String) -> Result<Option<String>, AppError> {
+        let slug = sqlx::query_scalar!(
+            "select a.slug from article_slug_history h join article a on a.id = h.article_id where h.slug = ?",
+            old_slug
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(slug)
+    }
+
+    async fn update_article_by_slug(
+        &self,
+        user_id: i64,
+        slug: String,
+        update_form: ArticleUpdateForm,
+    ) -> Result<(), AppError> {
+        let pool = &self.pool;
+        let mut tx = pool.begin().await?;
+        let (article_id, old_title) = sqlx::query!(
+            "select id, title from article where slug = ? and user_id = ? for update",
+            slug,
+            user_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .map(|row| (row.id, row.title))
+        .ok_or_else(|| AppError::NotFound("article".to_string()))?;
+
+        // 新标题对应的 slug 与当前不同时，旧 slug 进入历史表以便重定向
+        let new_slug = match &update_form.title {
+            Some(title) => {
+                let base = slug::base_slug(title);
+                if slug::keeps_slug(&slug, &old_title, title) {
+                    None
+                } else {
+                    let taken = taken_slugs(&mut tx, &base, article_id).await?;
+                    Some(slug::unique_slug(&base, &taken))
+                }
+            }
+            None => None,
+        };
+        if let Some(new_slug) = &new_slug {
+            sqlx::query!(
+                "delete from article_slug_history where slug = ? and article_id = ?",
+                new_slug,
+                article_id
+            )
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query!(
+                "insert into article_slug_history(created_at, article_id, slug) values (?, ?, ?)",
+                Utc::now().naive_utc(),
+                article_id,
+                slug
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        // updated_at 每次编辑都会刷新，所以 set 子句永远不为空
+        let mut query_builder = QueryBuilder::new("update article set updated_at = ");
+        query_builder.push_bind(Utc::now().naive_utc());
+        if let Some(new_slug) = new_slug {
+            query_builder.push(", slug = ");
+            query_builder.push_bind(new_slug);
+        }
+        if let Some(title) = update_form.title {
+            query_builder.push(", title = ");
+            query_builder.push_bind(title);
+        }
+        if let Some(body) = update_form.body {
+            query_builder.push(", body = ");
+            query_builder.push_bind(body);
+        }
+        if let Some(description) = update_form.description {
+            query_builder.push(", description = ");
+            query_builder.push_bind(description);
+        }
+        if let Some(tag_list) = &update_form.tag_list {
+            query_builder.push(", tag_list = ");
+            query_builder.push_bind(serde_json::to_string(tag_list).unwrap_or("[]".to_string()));
+        }
+        query_builder.push(" where id = ");
+        query_builder.push_bind(article_id);
+        query_builder.build().execute(&mut *tx).await?;
+
+        // 标签整体替换，article_tags 与 tag_list 列保持一致
+        if let Some(tag_list) = update_form.tag_list {
+            sqlx::query!("delete from article_tags where article_id = ?", article_id)
+                .execute(&mut *tx)
+                .await?;
+            for tag in tag_list {
+                insert_tag(&mut tx, tag, article_id).await?;
+            }
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Removes the article together with its tags, favorites and comments.
+    async fn delete_article_by_slug(
+        &self,
+        user_id: i64,
+        slug: String,
+    ) -> Result<(), AppError> {
+        let pool = &self.pool;
+        let mut tx = pool.begin().await?;
+        let article_id = sqlx::query_scalar!(
+            "select id from article where slug = ? and user_id = ? for update",
+            slug,
+            user_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("article".to_string()))?;
+
+        sqlx::query!("delete from article_tags where article_id = ?", article_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!("delete from article_favorite where article_id = ?", article_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!("delete from comment where article_id = ?", article_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!("delete from article_slug_history where article_id = ?", article_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!("delete from article where id = ?", article_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn insert_article_favorite(
+        &self,
+        user_id: i64,
+        article_id: i64,
+    ) -> Result<i64, AppError> {
+        let pool = &self.pool;
+        let result = sqlx::query!(
+            "insert article_favorite(created_at, updated_at, user_id, article_id) values (?, ?, ?, ?)",
+            chrono::Utc::now().naive_utc(),
+            chrono::Utc::now().naive_utc(),
+            user_id,
+            article_id
+        )
+        .execute(pool)
+        .await?;
+
+        if result.last_insert_id() > 0 {
+            Ok(result.last_insert_id() as i64)
+        } else {
+            Err(AppError::Internal("insert article favorite failed".to_string()))
+        }
+    }
+
+    async fn select_article_favorite(
+        &self,
+        user_id: Option<i64>,
+        article_id: i64,
+    ) -> Result<bool, AppError> {
+        let pool = &self.pool;
+        let result = sqlx::query_as!(
+            ArticleFavoriteEntity,
+            "select user_id, article_id from article_favorite where user_id = ? and article_id = ?",
+            user_id,
+            article_id
+        )
+        .fetch_optional(pool)
+        .await?;
+        if result.is_some() {
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Returns which of `article_ids` the user has favorited, with a single query.
+    async fn select_favorited_article_ids(
+        &self,
+        user_id: i64,
+        article_ids: &[i64],
+    ) -> Result<HashSet<i64>, AppError> {
+        let pool = &self.pool;
+        if article_ids.is_empty() {
+            return Ok(HashSet::new());
+        }
+        let mut query_builder =
+            QueryBuilder::new("select article_id from article_favorite where user_id = ");
+        query_builder.push_bind(user_id);
+        query_builder.push(" and article_id in (");
+        let mut separated = query_builder.separated(", ");
+        for id in article_ids {
+            separated.push_bind(*id);
+        }
+        separated.push_unseparated(")");
+
+        let ids = query_builder
+            .build_query_scalar::<i64>()
+            .fetch_all(pool)
+            .await?;
+        Ok(ids.into_iter().collect())
+    }
+
+    async fn delete_article_favorite(
+        &self,
+        user_id: i64,
+        article_id: i64,
+    ) -> Result<(), AppError> {
+        let pool = &self.pool;
+        let result = sqlx::query!(
+            "delete from article_favorite where user_id = ? and article_id = ?",
+            user_id,
+            article_id
+        )
+        .execute(pool)
+        .await?;
+
+        if result.rows_affected() > 0 {
+            Ok(())
+        } else {
+            Err(AppError::NotFound("favorite".to_string()))
+        }
+    }
+}
+
+/// Slugs in the `base` family that belong to other articles, currently or in
+/// their history. `article_id` may reuse its own former slugs.
+async fn taken_slugs(
+    conn: &mut MySqlConnection,
+    base: &str,
+    article_id: i64,
+) -> Result<HashSet<String>, AppError> {
+    let pattern = slug::suffixed_pattern(base);
+    let slugs = sqlx::query_scalar::<_, String>(
+        "select slug from article where slug = ? or slug like ?
+        union select slug from article_slug_history where article_id <> ? and (slug = ? or slug like ?)",
+    )
+    .bind(base)
+    .bind(&pattern)
+    .bind(article_id)
+    .bind(base)
+    .bind(&pattern)
+    .fetch_all(conn)
+    .await?;
+    Ok(slugs.into_iter().collect())
+}
+
+/// Boolean-mode `against` string matching any term as a word prefix:
+/// `rust* async*`.
+fn boolean_query(terms: &[String]) -> String {
+    terms
+        .iter()
+        .map(|term| format!("{}*", term))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Builds the `order by` terms for `window.sort`. Only fixed SQL is produced;
+/// the trending cutoffs are returned as values to bind.
+fn article_order(window: &PageWindow) -> (String, Vec<String>) {
+    let mut values = vec![];
+    let sql = match window.sort {
+        ArticleSort::Latest | ArticleSort::Oldest => {
+            return (
+                format!("a.created_at {order}, a.id {order}", order = window.order()),
+                values,
+            );
+        }
+        ArticleSort::MostFavorited => "favorites_count desc".to_string(),
+        ArticleSort::MostCommented => {
+            "(select count(*) from comment c where c.article_id = a.id) desc".to_string()
+        }
+        ArticleSort::Trending => {
+            let terms: Vec<&str> = trending_cutoffs()
+                .iter()
+                .map(|cutoff| {
+                    values.push(cutoff.format("%Y-%m-%d %H:%M:%S%.6f").to_string());
+                    "sum(case when af.created_at >= ? then 1 else 0 end)"
+                })
+                .collect();
+            format!("({}) desc", terms.join(" + "))
+        }
+    };
+    // 同分时新文章在前
+    (format!("{}, a.created_at desc, a.id desc", sql), values)
+}
+
+/// Builds the `where` clause shared by the page query and the count query.
+fn article_filters(query: &ArticleQuery) -> (String, Vec<String>) {
+    let mut conditions: Vec<String> = vec![];
+    let mut values = vec![];
+    if !query.authors.is_empty() {
+        conditions.push(format!(
+            "a.user_id in (select id from user where username in {})",
+            placeholders(query.authors.len())
+        ));
+        values.extend(query.authors.iter().cloned());
+    }
+    if !query.tags.is_empty() {
+        let mut condition = format!(
+            "a.id in (select tg.article_id from article_tags tg join tags t on t.id = tg.tag_id where t.name in {}",
+            placeholders(query.tags.len())
+        );
+        values.extend(query.tags.iter().cloned());
+        if query.tag_match == TagMatch::All {
+            // 每个标签都要有，标签已去重
+            condition.push_str(" group by tg.article_id having count(*) = ?");
+            values.push(query.tags.len().to_string());
+        }
+        condition.push(')');
+        conditions.push(condition);
+    }
+    if let Some(favorited) = &query.favorited {
+        conditions.push("a.id in (select article_id from article_favorite af join user on af.user_id = user.id where user.username = ?)".to_string());
+        values.push(favorited.clone());
+    }
+    if let Some(since) = query.since {
+        conditions.push("a.created_at >= ?".to_string());
+        values.push(since.format("%Y-%m-%d %H:%M:%S%.6f").to_string());
+    }
+    if let Some(until) = query.until {
+        conditions.push("a.created_at < ?".to_string());
+        values.push(until.format("%Y-%m-%d %H:%M:%S%.6f").to_string());
+    }
+    if let Some(viewer_id) = query.viewer_id.filter(|_| query.exclude_own) {
+        conditions.push("a.user_id <> ?".to_string());
+        values.push(viewer_id.to_string());
+    }
+    if let Some(feed_user_id) = query.feed_user_id {
+        conditions.push("a.user_id in (select uf.followee_user_id from user_follow uf join user on uf.follower_user_id = user.id where user.id = ?)".to_string());
+        values.push(feed_user_id.to_string());
+    }
+
+    if conditions.is_empty() {
+        return (String::new(), values);
+    }
+    (format!(" where {} ", conditions.join(" and ")), values)
+}
+
+/// `(?, ?, ..)` with `n` placeholders.
+fn placeholders(n: usize) -> String {
+    format!("({})", vec!["?"; n].join(", "))
+}
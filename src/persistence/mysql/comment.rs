@@ -0,0 +1,80 @@
+use async_trait::async_trait;
+
+use crate::error::AppError;
+use crate::models::comment::CommentEntity;
+use crate::persistence::CommentRepo;
+
+use super::MySqlRepository;
+
+#[async_trait]
+impl CommentRepo for MySqlRepository {
+    async fn select_comments_by_article_id(
+        &self,
+        article_id: i64,
+    ) -> Result<Vec<CommentEntity>, AppError> {
+        let pool = &self.pool;
+        let comments = sqlx::query_as!(
+            CommentEntity,
+            "select * from comment where article_id = ? order by id desc",
+            article_id,
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(comments)
+    }
+
+    async fn get_comment_by_id(
+        &self,
+        comment_id: i64,
+    ) -> Result<CommentEntity, AppError> {
+        let pool = &self.pool;
+        let comment = sqlx::query_as!(
+            CommentEntity,
+            "select * from comment where id = ? limit 1",
+            comment_id,
+        )
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("comment".to_string()))?;
+        Ok(comment)
+    }
+
+    async fn insert_article_comment(
+        &self,
+        user_id: i64,
+        body: String,
+        article_id: i64,
+    ) -> Result<i64, AppError> {
+        let pool = &self.pool;
+        let result = sqlx::query!(
+            "INSERT INTO comment (created_at, updated_at, body, user_id, article_id) VALUES (?, ?, ?, ?, ?)",
+            chrono::Utc::now().naive_utc(),
+            chrono::Utc::now().naive_utc(),
+            body,
+            user_id,
+            article_id
+        )
+        .execute(pool)
+        .await?;
+        if result.last_insert_id() > 0 {
+            Ok(result.last_insert_id() as i64)
+        } else {
+            Err(AppError::Internal("insert comment failed".to_string()))
+        }
+    }
+
+    async fn delete_comment_by_id(
+        &self,
+        comment_id: i64,
+    ) -> Result<(), AppError> {
+        let pool = &self.pool;
+        let reuslt = sqlx::query!("DELETE FROM comment WHERE id = ?", comment_id)
+            .execute(pool)
+            .await?;
+        if reuslt.rows_affected() > 0 {
+            Ok(())
+        } else {
+            Err(AppError::NotFound("comment".to_string()))
+        }
+    }
+}
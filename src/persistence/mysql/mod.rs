@@ -0,0 +1,48 @@
+use sqlx::migrate::Migrator;
+use sqlx::mysql::MySqlPoolOptions;
+use sqlx::MySqlPool;
+
+use crate::config::DatabaseConfig;
+
+mod article;
+mod comment;
+mod session;
+mod tag;
+mod user;
+
+/// Versioned schema migrations embedded from `./migrations/mysql` at compile time.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations/mysql");
+
+/// MySQL implementation of the repository traits.
+#[derive(Debug, Clone)]
+pub struct MySqlRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlRepository {
+    pub fn new(pool: MySqlPool) -> Self {
+        MySqlRepository { pool }
+    }
+
+    /// Connects using the pool settings from `config` and, when `migrate` is
+    /// set, applies pending migrations.
+    pub async fn connect(config: &DatabaseConfig, migrate: bool) -> Result<Self, sqlx::Error> {
+        // We create a single connection pool for SQLx that's shared across the whole application.
+        // This saves us from opening a new connection for every API call, which is wasteful.
+        let pool = MySqlPoolOptions::new()
+            .min_connections(config.min_connections)
+            .max_connections(config.max_connections)
+            .acquire_timeout(config.acquire_timeout())
+            .idle_timeout(config.idle_timeout())
+            .connect(&config.url)
+            .await?;
+
+        // 启动时自动执行未应用的迁移，保证表结构与代码一致
+        if migrate {
+            log::info!("running database migrations");
+            MIGRATOR.run(&pool).await?;
+        }
+
+        Ok(MySqlRepository::new(pool))
+    }
+}
@@ -0,0 +1,74 @@
+use async_trait::async_trait;
+use sqlx::{MySql, MySqlConnection, QueryBuilder};
+
+use crate::error::AppError;
+use crate::models::TagQuery;
+use crate::persistence::{prefix_pattern, TagRepo};
+
+use super::MySqlRepository;
+
+#[async_trait]
+impl TagRepo for MySqlRepository {
+    async fn select_popular_tags(&self, query: TagQuery) -> Result<Vec<String>, AppError> {
+        let mut query_builder = QueryBuilder::<MySql>::new(
+            "select t.name from tags t join article_tags tg on tg.tag_id = t.id",
+        );
+        if let Some(prefix) = query.prefix.filter(|prefix| !prefix.is_empty()) {
+            query_builder.push(" where t.name like ");
+            query_builder.push_bind(prefix_pattern(&prefix));
+            query_builder.push(" escape '!'");
+        }
+        query_builder.push(" group by t.id, t.name order by count(*) desc, t.name");
+        if let Some(limit) = query.limit {
+            query_builder.push(" limit ");
+            query_builder.push_bind(limit.max(0) as i64);
+        }
+        let tags = query_builder
+            .build_query_scalar::<String>()
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(tags)
+    }
+
+    async fn select_tags_by_prefix(
+        &self,
+        prefix: &str,
+        limit: i64,
+    ) -> Result<Vec<String>, AppError> {
+        // 默认的 utf8mb4 排序规则不区分大小写，like 前缀直接走 uk_name
+        let tags = sqlx::query_scalar!(
+            "select t.name from tags t join article_tags tg on tg.tag_id = t.id where t.name like ? escape '!' group by t.id, t.name order by count(*) desc, t.name limit ?",
+            prefix_pattern(prefix),
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(tags)
+    }
+}
+
+/// Links the tag called `name` to the article, creating the tag on first use.
+pub(super) async fn insert_tag(
+    conn: &mut MySqlConnection,
+    name: String,
+    article_id: i64,
+) -> Result<i64, AppError> {
+    sqlx::query!(
+        "insert ignore into tags (created_at, name) values (?, ?)",
+        chrono::Utc::now().naive_utc(),
+        name
+    )
+    .execute(&mut *conn)
+    .await?;
+    let tag_id = sqlx::query_scalar!("select id from tags where name = ?", name)
+        .fetch_one(&mut *conn)
+        .await?;
+    sqlx::query!(
+        "insert ignore into article_tags (article_id, tag_id) values (?, ?)",
+        article_id,
+        tag_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(tag_id)
+}
@@ -0,0 +1,309 @@
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, Utc};
+use sqlx::{Execute, QueryBuilder};
+
+use crate::{
+    error::AppError,
+    models::user::{LoginFailures, UserEntity, UserFollowEntity, UserUpdateForm},
+    persistence::{FollowRepo, UserRepo},
+    utils::encrypt_password,
+};
+
+use super::MySqlRepository;
+
+#[async_trait]
+impl UserRepo for MySqlRepository {
+    async fn insert_user(
+        &self,
+        username: String,
+        email: String,
+        password: String,
+    ) -> Result<u64, AppError> {
+        let pool = &self.pool;
+        let hash_password = encrypt_password(password);
+
+        let result = sqlx::query!(
+            "INSERT INTO user (created_at, updated_at, username, email, password) VALUES (?, ?, ?, ?, ?)",
+            Utc::now().naive_utc(),
+            Utc::now().naive_utc(),
+            username,
+            email,
+            hash_password,
+        )
+        .execute(pool)
+        .await?;
+
+        if result.last_insert_id() > 0 {
+            Ok(result.last_insert_id())
+        } else {
+            Err(AppError::Internal("insert user failed".to_string()))
+        }
+    }
+
+    async fn select_user_by_id(&self, id: i64) -> Result<UserEntity, AppError> {
+        let pool = &self.pool;
+        let user = sqlx::query_as!(
+            UserEntity,
+            "SELECT id, username, email, password, image, bio FROM user WHERE id = ? limit 1",
+            (id)
+        )
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("user".to_string()))?;
+
+        Ok(user)
+    }
+
+    /// Loads all users in `ids` with a single query.
+    async fn select_users_by_ids(
+        &self,
+        ids: &[i64],
+    ) -> Result<Vec<UserEntity>, AppError> {
+        let pool = &self.pool;
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut query_builder = QueryBuilder::new(
+            "SELECT id, username, email, password, image, bio FROM user WHERE id IN (",
+        );
+        let mut separated = query_builder.separated(", ");
+        for id in ids {
+            separated.push_bind(*id);
+        }
+        separated.push_unseparated(")");
+
+        let users = query_builder
+            .build_query_as::<UserEntity>()
+            .fetch_all(pool)
+            .await?;
+        Ok(users)
+    }
+
+    //
+    async fn select_user_by_email(
+        &self,
+        email: String,
+    ) -> Result<UserEntity, AppError> {
+        let pool = &self.pool;
+        let user = sqlx::query_as!(
+            UserEntity,
+            "SELECT id, username, email, password, image, bio FROM user WHERE email = ? limit 1",
+            (email)
+        )
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("user".to_string()))?;
+        Ok(user)
+    }
+
+    async fn select_user_by_username(
+        &self,
+        username: String,
+    ) -> Result<UserEntity, AppError> {
+        let pool = &self.pool;
+        let user = sqlx::query_as!(
+            UserEntity,
+            "SELECT id, username, email, password, image, bio FROM user WHERE username = ? order by id desc limit 1",
+            (username)
+        )
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("user".to_string()))?;
+        Ok(user)
+    }
+
+    //
+    async fn update_user_by_id(
+        &self,
+        id: i64,
+        update_form: UserUpdateForm,
+    ) -> Result<(), AppError> {
+        let pool = &self.pool;
+        // let() mut conn = pool.get_conn()?;
+
+        // 设置要更新的字段和对应的值
+        let mut fields_values = vec![];
+        if update_form.username.is_some() {
+            fields_values.push(("username", update_form.username.unwrap()))
+        }
+        if update_form.email.is_some() {
+            fields_values.push(("email", update_form.email.unwrap()))
+        }
+        if update_form.password.is_some() {
+            fields_values.push(("password", encrypt_password(update_form.password.unwrap())));
+        }
+        if update_form.bio.is_some() {
+            fields_values.push(("bio", update_form.bio.unwrap()))
+        }
+        if update_form.image.is_some() {
+            fields_values.push(("image", update_form.image.unwrap()))
+        }
+
+        // 构建 SQL 更新语句
+        let mut query_builder = QueryBuilder::new("UPDATE user SET ");
+        for (i, (column, value)) in fields_values.iter().enumerate() {
+            if i > 0 {
+                query_builder.push(", ");
+            }
+            query_builder.push(column);
+            query_builder.push(" = ");
+            query_builder.push_bind(value);
+        }
+
+        query_builder.push(" WHERE id = ");
+        query_builder.push_bind(id);
+
+        let query = query_builder.build();
+        log::info!("update user sql: {:?}", query.sql());
+        let t = query.execute(pool).await?;
+
+        if t.rows_affected() > 0 {
+            log::info!("update user success");
+            Ok(())
+        } else {
+            log::info!("update user error");
+            Err(AppError::NotFound("user".to_string()))
+        }
+    }
+
+    async fn select_login_failures(&self, email: &str) -> Result<LoginFailures, AppError> {
+        let failures = sqlx::query_as!(
+            LoginFailures,
+            "select failed_login_count, locked_until from user where email = ?",
+            email
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(failures.unwrap_or_default())
+    }
+
+    async fn record_login_failure(&self, email: &str) -> Result<i32, AppError> {
+        // 原子自增，并发的失败尝试不会丢计数
+        sqlx::query!(
+            "update user set failed_login_count = failed_login_count + 1 where email = ?",
+            email
+        )
+        .execute(&self.pool)
+        .await?;
+        let failures = self.select_login_failures(email).await?;
+        Ok(failures.failed_login_count)
+    }
+
+    async fn lock_user(&self, email: &str, until: Option<NaiveDateTime>) -> Result<(), AppError> {
+        sqlx::query!(
+            "update user set locked_until = coalesce(?, locked_until) where email = ?",
+            until,
+            email
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn reset_login_failures(&self, user_id: i64) -> Result<(), AppError> {
+        sqlx::query!(
+            "update user set failed_login_count = 0, locked_until = null where id = ?",
+            user_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FollowRepo for MySqlRepository {
+    async fn select_follow_by_user(
+        &self,
+        follower_user_id: i64,
+        followee_user_id: i64,
+    ) -> Result<bool, AppError> {
+        let pool = &self.pool;
+        let result = sqlx::query_as!(
+            UserFollowEntity,
+            "select followee_user_id, follower_user_id from user_follow where follower_user_id = ? and followee_user_id = ?",
+            follower_user_id,
+            followee_user_id)
+            .fetch_optional(pool)
+            .await?;
+        if result.is_some() {
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Returns which of `user_ids` the follower follows, with a single query.
+    async fn select_following_ids(
+        &self,
+        follower_user_id: i64,
+        user_ids: &[i64],
+    ) -> Result<HashSet<i64>, AppError> {
+        let pool = &self.pool;
+        if user_ids.is_empty() {
+            return Ok(HashSet::new());
+        }
+        let mut query_builder = QueryBuilder::new(
+            "select followee_user_id from user_follow where follower_user_id = ",
+        );
+        query_builder.push_bind(follower_user_id);
+        query_builder.push(" and followee_user_id in (");
+        let mut separated = query_builder.separated(", ");
+        for id in user_ids {
+            separated.push_bind(*id);
+        }
+        separated.push_unseparated(")");
+
+        let ids = query_builder
+            .build_query_scalar::<i64>()
+            .fetch_all(pool)
+            .await?;
+        Ok(ids.into_iter().collect())
+    }
+
+    async fn insert_follow_by_user(
+        &self,
+        user_id: i64,
+        followee_user_id: i64,
+    ) -> Result<i64, AppError> {
+        let pool = &self.pool;
+        let result = sqlx::query!(
+            "insert user_follow(created_at, updated_at, follower_user_id, followee_user_id) values (?, ?, ?, ?)",
+            chrono::Utc::now().naive_utc(),
+            chrono::Utc::now().naive_utc(),
+            user_id,
+            followee_user_id
+        )
+        .execute(pool)
+        .await?;
+
+        if result.last_insert_id() > 0 {
+            Ok(result.last_insert_id() as i64)
+        } else {
+            Err(AppError::Internal("insert follow failed".to_string()))
+        }
+    }
+
+    async fn delete_follow_by_user(
+        &self,
+        user_id: i64,
+        followee_user_id: i64,
+    ) -> Result<(), AppError> {
+        let pool = &self.pool;
+        let result = sqlx::query!(
+            "delete from user_follow where follower_user_id = ? and followee_user_id = ?",
+            user_id,
+            followee_user_id
+        )
+        .execute(pool)
+        .await?;
+
+        if result.rows_affected() > 0 {
+            Ok(())
+        } else {
+            Err(AppError::NotFound("follow".to_string()))
+        }
+    }
+}
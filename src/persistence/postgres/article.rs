@@ -0,0 +1,480 @@
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use sqlx::{PgConnection, Postgres, QueryBuilder};
+
+use crate::error::AppError;
+use crate::models::article::{
+    ArticleCreateForm, ArticleEntity, ArticlePage, ArticleQuery, ArticleSort, ArticleUpdateForm,
+    TagMatch,
+};
+use crate::persistence::{trending_cutoffs, ArticleRepo, PageWindow};
+
+use crate::slug;
+
+use super::{now, tag::insert_tag, PostgresRepository};
+
+const SELECT_ARTICLE: &str = "SELECT a.id, a.title, a.slug, a.description, a.body, a.created_at, a.updated_at, a.tag_list, a.user_id, count(af.id) as favorites_count
+    FROM article a left join article_favorite af on a.id = af.article_id";
+
+#[async_trait]
+impl ArticleRepo for PostgresRepository {
+    async fn insert_article(
+        &self,
+        create_form: ArticleCreateForm,
+        user_id: i64,
+    ) -> Result<u64, AppError> {
+        let title = create_form.title;
+
+        // 文章和标签在同一个事务里写入，任何一步失败都整体回滚
+        let mut tx = self.pool.begin().await?;
+        let base = slug::base_slug(&title);
+        let slug = slug::unique_slug(&base, &taken_slugs(&mut tx, &base, 0).await?);
+        let article_id = sqlx::query_scalar::<_, i64>(
+            "insert into article(title, slug, description, body, created_at, updated_at, tag_list, user_id) values ($1, $2, $3, $4, $5, $6, $7, $8) returning id",
+        )
+        .bind(&title)
+        .bind(slug)
+        .bind(create_form.description)
+        .bind(create_form.body)
+        .bind(now())
+        .bind(now())
+        .bind(serde_json::to_string(&create_form.tag_list).unwrap_or("[]".to_string()))
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for tag in create_form.tag_list {
+            insert_tag(&mut tx, tag, article_id).await?;
+        }
+        tx.commit().await?;
+
+        Ok(article_id as u64)
+    }
+
+    async fn select_articles_by_query(&self, query: ArticleQuery) -> Result<ArticlePage, AppError> {
+        let window = PageWindow::from_query(&query)?;
+
+        let mut query_builder = QueryBuilder::<Postgres>::new(SELECT_ARTICLE);
+        let mut has_where = push_article_filters(&mut query_builder, &query);
+        if let Some((cursor, operator)) = window.keyset() {
+            // 向前翻页时按反方向取离游标最近的一页，再反转
+            push_conjunction(&mut query_builder, &mut has_where);
+            query_builder.push(format!("(a.created_at {} ", operator));
+            query_builder.push_bind(cursor.created_at);
+            query_builder.push(" or (a.created_at = ");
+            query_builder.push_bind(cursor.created_at);
+            query_builder.push(format!(" and a.id {} ", operator));
+            query_builder.push_bind(cursor.id);
+            query_builder.push("))");
+        }
+        query_builder.push(" group by a.id order by ");
+        push_order(&mut query_builder, &window);
+        query_builder.push(" limit ");
+        query_builder.push_bind(window.fetch_limit());
+        query_builder.push(" offset ");
+        query_builder.push_bind(window.offset);
+
+        let articles = query_builder
+            .build_query_as::<ArticleEntity>()
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut count_builder = QueryBuilder::<Postgres>::new("SELECT count(*) FROM article a");
+        push_article_filters(&mut count_builder, &query);
+        let articles_count = count_builder
+            .build_query_scalar::<i64>()
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(window.finish(articles, articles_count))
+    }
+
+    async fn search_articles(
+        &self,
+        terms: &[String],
+        query: ArticleQuery,
+    ) -> Result<ArticlePage, AppError> {
+        let window = PageWindow::from_query(&query)?;
+        let ts_query = ts_query(terms);
+
+        let mut query_builder = QueryBuilder::<Postgres>::new(SELECT_ARTICLE);
+        query_builder.push(
+            " join (select id, ts_rank(search_vector, q) as rank from article, to_tsquery('simple', ",
+        );
+        query_builder.push_bind(ts_query.clone());
+        query_builder.push(") q where search_vector @@ q) m on m.id = a.id");
+        push_article_filters(&mut query_builder, &query);
+        query_builder.push(" group by a.id, m.rank order by m.rank desc, a.id desc limit ");
+        query_builder.push_bind(window.limit as i64);
+        query_builder.push(" offset ");
+        query_builder.push_bind(window.offset);
+
+        let articles = query_builder
+            .build_query_as::<ArticleEntity>()
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut count_builder = QueryBuilder::<Postgres>::new(
+            "SELECT count(*) FROM article a join (select id from article where search_vector @@ to_tsquery('simple', ",
+        );
+        count_builder.push_bind(ts_query);
+        count_builder.push(")) m on m.id = a.id");
+        push_article_filters(&mut count_builder, &query);
+        let articles_count = count_builder
+            .build_query_scalar::<i64>()
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(PageWindow::finish_ranked(articles, articles_count))
+    }
+
+    async fn select_article_by_id(&self, id: u64) -> Result<ArticleEntity, AppError> {
+        let sql = format!("{} WHERE a.id = $1 group by a.id", SELECT_ARTICLE);
+        sqlx::query_as::<_, ArticleEntity>(&sql)
+            .bind(id as i64)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("article".to_string()))
+    }
+
+    async fn select_article_by_slug(&self, slug: String) -> Result<ArticleEntity, AppError> {
+        let sql = format!("{} WHERE a.slug = $1 group by a.id", SELECT_ARTICLE);
+        sqlx::query_as::<_, ArticleEntity>(&sql)
+            .bind(slug)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("article".to_string()))
+    }
+
+    async fn select_current_slug(&self, old_slug: String) -> Result<Option<String>, AppError> {
+        let slug = sqlx::query_scalar::<_, String>(
+            "select a.slug from article_slug_history h join article a on a.id = h.article_id where h.slug = $1",
+        )
+        .bind(old_slug)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(slug)
+    }
+
+    async fn update_article_by_slug(
+        &self,
+        user_id: i64,
+        slug: String,
+        update_form: ArticleUpdateForm,
+    ) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+        let (article_id, old_title) = sqlx::query_as::<_, (i64, String)>(
+            "select id, title from article where slug = $1 and user_id = $2 for update",
+        )
+        .bind(&slug)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("article".to_string()))?;
+
+        // 新标题对应的 slug 与当前不同时，旧 slug 进入历史表以便重定向
+        let new_slug = match &update_form.title {
+            Some(title) => {
+                let base = slug::base_slug(title);
+                if slug::keeps_slug(&slug, &old_title, title) {
+                    None
+                } else {
+                    let taken = taken_slugs(&mut tx, &base, article_id).await?;
+                    Some(slug::unique_slug(&base, &taken))
+                }
+            }
+            None => None,
+        };
+        if let Some(new_slug) = &new_slug {
+            sqlx::query("delete from article_slug_history where slug = $1 and article_id = $2")
+                .bind(new_slug)
+                .bind(article_id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query(
+                "insert into article_slug_history(created_at, article_id, slug) values ($1, $2, $3)",
+            )
+            .bind(now())
+            .bind(article_id)
+            .bind(&slug)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        // updated_at 每次编辑都会刷新，所以 set 子句永远不为空
+        let mut query_builder = QueryBuilder::<Postgres>::new("update article set updated_at = ");
+        query_builder.push_bind(now());
+        if let Some(new_slug) = new_slug {
+            query_builder.push(", slug = ");
+            query_builder.push_bind(new_slug);
+        }
+        if let Some(title) = update_form.title {
+            query_builder.push(", title = ");
+            query_builder.push_bind(title);
+        }
+        if let Some(body) = update_form.body {
+            query_builder.push(", body = ");
+            query_builder.push_bind(body);
+        }
+        if let Some(description) = update_form.description {
+            query_builder.push(", description = ");
+            query_builder.push_bind(description);
+        }
+        if let Some(tag_list) = &update_form.tag_list {
+            query_builder.push(", tag_list = ");
+            query_builder.push_bind(serde_json::to_string(tag_list).unwrap_or("[]".to_string()));
+        }
+        query_builder.push(" where id = ");
+        query_builder.push_bind(article_id);
+        query_builder.build().execute(&mut *tx).await?;
+
+        // 标签整体替换，article_tags 与 tag_list 列保持一致
+        if let Some(tag_list) = update_form.tag_list {
+            sqlx::query("delete from article_tags where article_id = $1")
+                .bind(article_id)
+                .execute(&mut *tx)
+                .await?;
+            for tag in tag_list {
+                insert_tag(&mut tx, tag, article_id).await?;
+            }
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Removes the article together with its tags, favorites and comments.
+    async fn delete_article_by_slug(&self, user_id: i64, slug: String) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+        let article_id = sqlx::query_scalar::<_, i64>(
+            "select id from article where slug = $1 and user_id = $2 for update",
+        )
+        .bind(slug)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("article".to_string()))?;
+
+        for sql in [
+            "delete from article_tags where article_id = $1",
+            "delete from article_favorite where article_id = $1",
+            "delete from comment where article_id = $1",
+            "delete from article_slug_history where article_id = $1",
+            "delete from article where id = $1",
+        ] {
+            sqlx::query(sql).bind(article_id).execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn insert_article_favorite(
+        &self,
+        user_id: i64,
+        article_id: i64,
+    ) -> Result<i64, AppError> {
+        let id = sqlx::query_scalar::<_, i64>(
+            "insert into article_favorite(created_at, updated_at, user_id, article_id) values ($1, $2, $3, $4) returning id",
+        )
+        .bind(now())
+        .bind(now())
+        .bind(user_id)
+        .bind(article_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    async fn select_article_favorite(
+        &self,
+        user_id: Option<i64>,
+        article_id: i64,
+    ) -> Result<bool, AppError> {
+        let result = sqlx::query_scalar::<_, i64>(
+            "select id from article_favorite where user_id = $1 and article_id = $2",
+        )
+        .bind(user_id)
+        .bind(article_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(result.is_some())
+    }
+
+    async fn select_favorited_article_ids(
+        &self,
+        user_id: i64,
+        article_ids: &[i64],
+    ) -> Result<HashSet<i64>, AppError> {
+        if article_ids.is_empty() {
+            return Ok(HashSet::new());
+        }
+        let mut query_builder = QueryBuilder::<Postgres>::new(
+            "select article_id from article_favorite where user_id = ",
+        );
+        query_builder.push_bind(user_id);
+        query_builder.push(" and article_id in (");
+        let mut separated = query_builder.separated(", ");
+        for id in article_ids {
+            separated.push_bind(*id);
+        }
+        separated.push_unseparated(")");
+
+        let ids = query_builder
+            .build_query_scalar::<i64>()
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(ids.into_iter().collect())
+    }
+
+    async fn delete_article_favorite(&self, user_id: i64, article_id: i64) -> Result<(), AppError> {
+        let result =
+            sqlx::query("delete from article_favorite where user_id = $1 and article_id = $2")
+                .bind(user_id)
+                .bind(article_id)
+                .execute(&self.pool)
+                .await?;
+
+        if result.rows_affected() > 0 {
+            Ok(())
+        } else {
+            Err(AppError::NotFound("favorite".to_string()))
+        }
+    }
+}
+
+/// Slugs in the `base` family that belong to other articles, currently or in
+/// their history. `article_id` may reuse its own former slugs.
+async fn taken_slugs(
+    conn: &mut PgConnection,
+    base: &str,
+    article_id: i64,
+) -> Result<HashSet<String>, AppError> {
+    let pattern = slug::suffixed_pattern(base);
+    let slugs = sqlx::query_scalar::<_, String>(
+        "select slug from article where slug = $1 or slug like $2
+        union select slug from article_slug_history where article_id <> $3 and (slug = $4 or slug like $5)",
+    )
+    .bind(base)
+    .bind(&pattern)
+    .bind(article_id)
+    .bind(base)
+    .bind(&pattern)
+    .fetch_all(conn)
+    .await?;
+    Ok(slugs.into_iter().collect())
+}
+
+/// `tsquery` matching any term as a word prefix: `rust:* | async:*`.
+fn ts_query(terms: &[String]) -> String {
+    terms
+        .iter()
+        .map(|term| format!("{}:*", term))
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+/// Appends the `order by` terms for `window.sort`. Only fixed SQL is pushed;
+/// the trending cutoffs are bound.
+fn push_order(query_builder: &mut QueryBuilder<'_, Postgres>, window: &PageWindow) {
+    match window.sort {
+        ArticleSort::Latest | ArticleSort::Oldest => {
+            query_builder.push(format!(
+                "a.created_at {order}, a.id {order}",
+                order = window.order()
+            ));
+            return;
+        }
+        ArticleSort::MostFavorited => {
+            query_builder.push("favorites_count desc");
+        }
+        ArticleSort::MostCommented => {
+            query_builder.push("(select count(*) from comment c where c.article_id = a.id) desc");
+        }
+        ArticleSort::Trending => {
+            query_builder.push("(");
+            for (i, cutoff) in trending_cutoffs().into_iter().enumerate() {
+                if i > 0 {
+                    query_builder.push(" + ");
+                }
+                query_builder.push("sum(case when af.created_at >= ");
+                query_builder.push_bind(cutoff);
+                query_builder.push(" then 1 else 0 end)");
+            }
+            query_builder.push(") desc");
+        }
+    }
+    // 同分时新文章在前
+    query_builder.push(", a.created_at desc, a.id desc");
+}
+
+fn push_conjunction(query_builder: &mut QueryBuilder<'_, Postgres>, has_where: &mut bool) {
+    query_builder.push(if *has_where { " and " } else { " where " });
+    *has_where = true;
+}
+
+/// Appends `(?, ?, ..)` binding each of `values`.
+fn push_list(query_builder: &mut QueryBuilder<'_, Postgres>, values: &[String]) {
+    query_builder.push("(");
+    let mut separated = query_builder.separated(", ");
+    for value in values {
+        separated.push_bind(value.clone());
+    }
+    query_builder.push(")");
+}
+
+/// Appends the `where` clause shared by the page query and the count query.
+/// Returns whether any condition was added.
+fn push_article_filters(
+    query_builder: &mut QueryBuilder<'_, Postgres>,
+    query: &ArticleQuery,
+) -> bool {
+    let mut has_where = false;
+    if !query.authors.is_empty() {
+        push_conjunction(query_builder, &mut has_where);
+        query_builder.push("a.user_id in (select id from users where username in ");
+        push_list(query_builder, &query.authors);
+        query_builder.push(")");
+    }
+    if !query.tags.is_empty() {
+        push_conjunction(query_builder, &mut has_where);
+        query_builder.push("a.id in (select tg.article_id from article_tags tg join tags t on t.id = tg.tag_id where t.name in ");
+        push_list(query_builder, &query.tags);
+        if query.tag_match == TagMatch::All {
+            // 每个标签都要有，标签已去重
+            query_builder.push(" group by tg.article_id having count(*) = ");
+            query_builder.push_bind(query.tags.len() as i64);
+        }
+        query_builder.push(")");
+    }
+    if let Some(favorited) = &query.favorited {
+        push_conjunction(query_builder, &mut has_where);
+        query_builder.push("a.id in (select article_id from article_favorite af join users u on af.user_id = u.id where u.username = ");
+        query_builder.push_bind(favorited.clone());
+        query_builder.push(")");
+    }
+    if let Some(since) = query.since {
+        push_conjunction(query_builder, &mut has_where);
+        query_builder.push("a.created_at >= ");
+        query_builder.push_bind(since);
+    }
+    if let Some(until) = query.until {
+        push_conjunction(query_builder, &mut has_where);
+        query_builder.push("a.created_at < ");
+        query_builder.push_bind(until);
+    }
+    if let Some(viewer_id) = query.viewer_id.filter(|_| query.exclude_own) {
+        push_conjunction(query_builder, &mut has_where);
+        query_builder.push("a.user_id <> ");
+        query_builder.push_bind(viewer_id);
+    }
+    if let Some(feed_user_id) = query.feed_user_id {
+        push_conjunction(query_builder, &mut has_where);
+        query_builder.push(
+            "a.user_id in (select followee_user_id from user_follow where follower_user_id = ",
+        );
+        query_builder.push_bind(feed_user_id);
+        query_builder.push(")");
+    }
+    has_where
+}
@@ -0,0 +1,65 @@
+use async_trait::async_trait;
+
+use crate::error::AppError;
+use crate::models::comment::CommentEntity;
+use crate::persistence::CommentRepo;
+
+use super::{now, PostgresRepository};
+
+#[async_trait]
+impl CommentRepo for PostgresRepository {
+    async fn select_comments_by_article_id(
+        &self,
+        article_id: i64,
+    ) -> Result<Vec<CommentEntity>, AppError> {
+        let comments = sqlx::query_as::<_, CommentEntity>(
+            "select id, body, created_at, updated_at, article_id, user_id from comment where article_id = $1 order by id desc",
+        )
+        .bind(article_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(comments)
+    }
+
+    async fn get_comment_by_id(&self, comment_id: i64) -> Result<CommentEntity, AppError> {
+        let comment = sqlx::query_as::<_, CommentEntity>(
+            "select id, body, created_at, updated_at, article_id, user_id from comment where id = $1 limit 1",
+        )
+        .bind(comment_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("comment".to_string()))?;
+        Ok(comment)
+    }
+
+    async fn insert_article_comment(
+        &self,
+        user_id: i64,
+        body: String,
+        article_id: i64,
+    ) -> Result<i64, AppError> {
+        let id = sqlx::query_scalar::<_, i64>(
+            "INSERT INTO comment (created_at, updated_at, body, user_id, article_id) VALUES ($1, $2, $3, $4, $5) RETURNING id",
+        )
+        .bind(now())
+        .bind(now())
+        .bind(body)
+        .bind(user_id)
+        .bind(article_id)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(id)
+    }
+
+    async fn delete_comment_by_id(&self, comment_id: i64) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM comment WHERE id = $1")
+            .bind(comment_id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() > 0 {
+            Ok(())
+        } else {
+            Err(AppError::NotFound("comment".to_string()))
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use chrono::{NaiveDateTime, SubsecRound, Utc};
+use sqlx::migrate::Migrator;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+
+use crate::config::DatabaseConfig;
+
+mod article;
+mod comment;
+mod session;
+mod tag;
+mod user;
+
+/// Versioned schema migrations embedded from `./migrations/postgres` at compile time.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations/postgres");
+
+/// PostgreSQL implementation of the repository traits. Users live in the
+/// `users` table since `user` is reserved.
+#[derive(Debug, Clone)]
+pub struct PostgresRepository {
+    pool: PgPool,
+}
+
+impl PostgresRepository {
+    pub fn new(pool: PgPool) -> Self {
+        PostgresRepository { pool }
+    }
+
+    /// Connects using the pool settings from `config` and, when `migrate` is
+    /// set, applies pending migrations.
+    pub async fn connect(config: &DatabaseConfig, migrate: bool) -> Result<Self, sqlx::Error> {
+        let pool = PgPoolOptions::new()
+            .min_connections(config.min_connections)
+            .max_connections(config.max_connections)
+            .acquire_timeout(config.acquire_timeout())
+            .idle_timeout(config.idle_timeout())
+            .connect(&config.url)
+            .await?;
+
+        if migrate {
+            log::info!("running database migrations");
+            MIGRATOR.run(&pool).await?;
+        }
+
+        Ok(PostgresRepository::new(pool))
+    }
+}
+
+/// Truncated to milliseconds like the `TIMESTAMP(3)` columns, so cursors built
+/// from a freshly inserted row match what is stored.
+fn now() -> NaiveDateTime {
+    Utc::now().naive_utc().trunc_subsecs(3)
+}
@@ -0,0 +1,108 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+
+use crate::error::AppError;
+use crate::models::session::{SessionDevice, SessionEntity};
+use crate::persistence::SessionRepo;
+
+use super::{now, PostgresRepository};
+
+#[async_trait]
+impl SessionRepo for PostgresRepository {
+    async fn insert_session(
+        &self,
+        user_id: i64,
+        refresh_token_hash: String,
+        device: SessionDevice,
+        expires_at: NaiveDateTime,
+    ) -> Result<i64, AppError> {
+        let now = now();
+        let id = sqlx::query_scalar::<_, i64>(
+            "insert into user_session (created_at, updated_at, user_id, refresh_token_hash, user_agent, ip, expires_at) values ($1, $2, $3, $4, $5, $6, $7) returning id",
+        )
+        .bind(now)
+        .bind(now)
+        .bind(user_id)
+        .bind(refresh_token_hash)
+        .bind(device.user_agent)
+        .bind(device.ip)
+        .bind(expires_at)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(id)
+    }
+
+    async fn select_session_by_id(&self, id: i64) -> Result<SessionEntity, AppError> {
+        sqlx::query_as::<_, SessionEntity>(
+            "select id, created_at, updated_at, user_id, refresh_token_hash, user_agent, ip, expires_at, revoked_at from user_session where id = $1",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("session".to_string()))
+    }
+
+    async fn select_session_by_refresh_token(
+        &self,
+        refresh_token_hash: &str,
+    ) -> Result<SessionEntity, AppError> {
+        sqlx::query_as::<_, SessionEntity>(
+            "select id, created_at, updated_at, user_id, refresh_token_hash, user_agent, ip, expires_at, revoked_at from user_session where refresh_token_hash = $1",
+        )
+        .bind(refresh_token_hash)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("session".to_string()))
+    }
+
+    async fn rotate_session(
+        &self,
+        id: i64,
+        old_hash: &str,
+        new_hash: String,
+        expires_at: NaiveDateTime,
+    ) -> Result<(), AppError> {
+        let result = sqlx::query(
+            "update user_session set refresh_token_hash = $1, expires_at = $2, updated_at = $3 where id = $4 and refresh_token_hash = $5 and revoked_at is null",
+        )
+        .bind(new_hash)
+        .bind(expires_at)
+        .bind(now())
+        .bind(id)
+        .bind(old_hash)
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() > 0 {
+            Ok(())
+        } else {
+            Err(AppError::NotFound("session".to_string()))
+        }
+    }
+
+    async fn revoke_session(&self, user_id: i64, id: i64) -> Result<(), AppError> {
+        let result = sqlx::query(
+            "update user_session set revoked_at = $1 where id = $2 and user_id = $3 and revoked_at is null",
+        )
+        .bind(now())
+        .bind(id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() > 0 {
+            Ok(())
+        } else {
+            Err(AppError::NotFound("session".to_string()))
+        }
+    }
+
+    async fn revoke_user_sessions(&self, user_id: i64) -> Result<u64, AppError> {
+        let result = sqlx::query(
+            "update user_session set revoked_at = $1 where user_id = $2 and revoked_at is null",
+        )
+        .bind(now())
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
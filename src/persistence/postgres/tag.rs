@@ -0,0 +1,72 @@
+use async_trait::async_trait;
+use sqlx::{PgConnection, Postgres, QueryBuilder};
+
+use crate::error::AppError;
+use crate::models::TagQuery;
+use crate::persistence::{prefix_pattern, TagRepo};
+
+use super::{now, PostgresRepository};
+
+#[async_trait]
+impl TagRepo for PostgresRepository {
+    async fn select_popular_tags(&self, query: TagQuery) -> Result<Vec<String>, AppError> {
+        let mut query_builder = QueryBuilder::<Postgres>::new(
+            "select t.name from tags t join article_tags tg on tg.tag_id = t.id",
+        );
+        if let Some(prefix) = query.prefix.filter(|prefix| !prefix.is_empty()) {
+            query_builder.push(" where t.name like ");
+            query_builder.push_bind(prefix_pattern(&prefix));
+            query_builder.push(" escape '!'");
+        }
+        query_builder.push(" group by t.id, t.name order by count(*) desc, t.name");
+        if let Some(limit) = query.limit {
+            query_builder.push(" limit ");
+            query_builder.push_bind(limit.max(0) as i64);
+        }
+        let tags = query_builder
+            .build_query_scalar::<String>()
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(tags)
+    }
+
+    async fn select_tags_by_prefix(
+        &self,
+        prefix: &str,
+        limit: i64,
+    ) -> Result<Vec<String>, AppError> {
+        // 与 idx_tags_lower_name 的表达式一致才能走索引
+        let tags = sqlx::query_scalar::<_, String>(
+            "select t.name from tags t join article_tags tg on tg.tag_id = t.id where lower(t.name) like $1 escape '!' group by t.id, t.name order by count(*) desc, t.name limit $2",
+        )
+        .bind(prefix_pattern(&prefix.to_lowercase()))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(tags)
+    }
+}
+
+/// Links the tag called `name` to the article, creating the tag on first use.
+pub(super) async fn insert_tag(
+    conn: &mut PgConnection,
+    name: String,
+    article_id: i64,
+) -> Result<i64, AppError> {
+    // do update 让已存在的标签也能 returning id
+    let tag_id = sqlx::query_scalar::<_, i64>(
+        "insert into tags (created_at, name) values ($1, $2) on conflict (name) do update set name = excluded.name returning id",
+    )
+    .bind(now())
+    .bind(name)
+    .fetch_one(&mut *conn)
+    .await?;
+    sqlx::query(
+        "insert into article_tags (article_id, tag_id) values ($1, $2) on conflict do nothing",
+    )
+    .bind(article_id)
+    .bind(tag_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(tag_id)
+}
@@ -0,0 +1,69 @@
+use async_trait::async_trait;
+
+use crate::error::AppError;
+use crate::models::comment::CommentEntity;
+use crate::persistence::CommentRepo;
+
+use super::{now, SqliteRepository};
+
+#[async_trait]
+impl CommentRepo for SqliteRepository {
+    async fn select_comments_by_article_id(
+        &self,
+        article_id: i64,
+    ) -> Result<Vec<CommentEntity>, AppError> {
+        let comments = sqlx::query_as::<_, CommentEntity>(
+            "select id, body, created_at, updated_at, article_id, user_id from comment where article_id = ? order by id desc",
+        )
+        .bind(article_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(comments)
+    }
+
+    async fn get_comment_by_id(&self, comment_id: i64) -> Result<CommentEntity, AppError> {
+        let comment = sqlx::query_as::<_, CommentEntity>(
+            "select id, body, created_at, updated_at, article_id, user_id from comment where id = ? limit 1",
+        )
+        .bind(comment_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("comment".to_string()))?;
+        Ok(comment)
+    }
+
+    async fn insert_article_comment(
+        &self,
+        user_id: i64,
+        body: String,
+        article_id: i64,
+    ) -> Result<i64, AppError> {
+        let result = sqlx::query(
+            "INSERT INTO comment (created_at, updated_at, body, user_id, article_id) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(now())
+        .bind(now())
+        .bind(body)
+        .bind(user_id)
+        .bind(article_id)
+        .execute(&self.pool)
+        .await?;
+        if result.last_insert_rowid() > 0 {
+            Ok(result.last_insert_rowid())
+        } else {
+            Err(AppError::Internal("insert comment failed".to_string()))
+        }
+    }
+
+    async fn delete_comment_by_id(&self, comment_id: i64) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM comment WHERE id = ?")
+            .bind(comment_id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() > 0 {
+            Ok(())
+        } else {
+            Err(AppError::NotFound("comment".to_string()))
+        }
+    }
+}
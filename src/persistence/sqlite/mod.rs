@@ -0,0 +1,69 @@
+use std::str::FromStr;
+
+use chrono::{NaiveDateTime, SubsecRound, Utc};
+use sqlx::migrate::Migrator;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::SqlitePool;
+
+use crate::config::DatabaseConfig;
+
+mod article;
+mod comment;
+mod session;
+mod tag;
+mod user;
+
+/// Versioned schema migrations embedded from `./migrations/sqlite` at compile time.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
+
+/// SQLite implementation of the repository traits, for running without any
+/// external services. `database.url` is a file (`sqlite://realworld.db`) or
+/// `sqlite::memory:`.
+#[derive(Debug, Clone)]
+pub struct SqliteRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        SqliteRepository { pool }
+    }
+
+    /// Connects using the pool settings from `config` and, when `migrate` is
+    /// set, applies pending migrations. The database file is created if missing.
+    pub async fn connect(config: &DatabaseConfig, migrate: bool) -> Result<Self, sqlx::Error> {
+        let options = SqliteConnectOptions::from_str(&config.url)?.create_if_missing(true);
+
+        // 每个 `:memory:` 连接都是独立的库，只能用一个永不回收的连接
+        let pool = if config.url.contains(":memory:") {
+            SqlitePoolOptions::new()
+                .min_connections(1)
+                .max_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+                .connect_with(options)
+                .await?
+        } else {
+            SqlitePoolOptions::new()
+                .min_connections(config.min_connections)
+                .max_connections(config.max_connections)
+                .acquire_timeout(config.acquire_timeout())
+                .idle_timeout(config.idle_timeout())
+                .connect_with(options)
+                .await?
+        };
+
+        if migrate {
+            log::info!("running database migrations");
+            MIGRATOR.run(&pool).await?;
+        }
+
+        Ok(SqliteRepository::new(pool))
+    }
+}
+
+/// Timestamps are stored as text, so they are truncated to milliseconds to
+/// keep the `created_at` comparisons used by cursors exact.
+fn now() -> NaiveDateTime {
+    Utc::now().naive_utc().trunc_subsecs(3)
+}
@@ -0,0 +1,71 @@
+use async_trait::async_trait;
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection};
+
+use crate::error::AppError;
+use crate::models::TagQuery;
+use crate::persistence::{prefix_pattern, TagRepo};
+
+use super::{now, SqliteRepository};
+
+#[async_trait]
+impl TagRepo for SqliteRepository {
+    async fn select_popular_tags(&self, query: TagQuery) -> Result<Vec<String>, AppError> {
+        let mut query_builder = QueryBuilder::<Sqlite>::new(
+            "select t.name from tags t join article_tags tg on tg.tag_id = t.id",
+        );
+        if let Some(prefix) = query.prefix.filter(|prefix| !prefix.is_empty()) {
+            query_builder.push(" where t.name like ");
+            query_builder.push_bind(prefix_pattern(&prefix));
+            query_builder.push(" escape '!'");
+        }
+        query_builder.push(" group by t.id, t.name order by count(*) desc, t.name");
+        if let Some(limit) = query.limit {
+            query_builder.push(" limit ");
+            query_builder.push_bind(limit.max(0) as i64);
+        }
+        let tags = query_builder
+            .build_query_scalar::<String>()
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(tags)
+    }
+
+    async fn select_tags_by_prefix(
+        &self,
+        prefix: &str,
+        limit: i64,
+    ) -> Result<Vec<String>, AppError> {
+        // like 默认不区分 ASCII 大小写，配合 idx_tags_name_nocase 走索引
+        let tags = sqlx::query_scalar::<_, String>(
+            "select t.name from tags t join article_tags tg on tg.tag_id = t.id where t.name like ? escape '!' group by t.id, t.name order by count(*) desc, t.name limit ?",
+        )
+        .bind(prefix_pattern(prefix))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(tags)
+    }
+}
+
+/// Links the tag called `name` to the article, creating the tag on first use.
+pub(super) async fn insert_tag(
+    conn: &mut SqliteConnection,
+    name: String,
+    article_id: i64,
+) -> Result<i64, AppError> {
+    sqlx::query("insert or ignore into tags (created_at, name) values (?, ?)")
+        .bind(now())
+        .bind(&name)
+        .execute(&mut *conn)
+        .await?;
+    let tag_id = sqlx::query_scalar::<_, i64>("select id from tags where name = ?")
+        .bind(&name)
+        .fetch_one(&mut *conn)
+        .await?;
+    sqlx::query("insert or ignore into article_tags (article_id, tag_id) values (?, ?)")
+        .bind(article_id)
+        .bind(tag_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(tag_id)
+}
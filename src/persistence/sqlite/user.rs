@@ -0,0 +1,265 @@
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use sqlx::{Execute, QueryBuilder, Sqlite};
+
+use crate::{
+    error::AppError,
+    models::user::{LoginFailures, UserEntity, UserUpdateForm},
+    persistence::{FollowRepo, UserRepo},
+    utils::encrypt_password,
+};
+
+use super::{now, SqliteRepository};
+
+#[async_trait]
+impl UserRepo for SqliteRepository {
+    async fn insert_user(
+        &self,
+        username: String,
+        email: String,
+        password: String,
+    ) -> Result<u64, AppError> {
+        let hash_password = encrypt_password(password);
+
+        let result = sqlx::query(
+            "INSERT INTO user (created_at, updated_at, username, email, password) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(now())
+        .bind(now())
+        .bind(username)
+        .bind(email)
+        .bind(hash_password)
+        .execute(&self.pool)
+        .await?;
+
+        if result.last_insert_rowid() > 0 {
+            Ok(result.last_insert_rowid() as u64)
+        } else {
+            Err(AppError::Internal("insert user failed".to_string()))
+        }
+    }
+
+    async fn select_user_by_id(&self, id: i64) -> Result<UserEntity, AppError> {
+        let user = sqlx::query_as::<_, UserEntity>(
+            "SELECT id, username, email, password, image, bio FROM user WHERE id = ? limit 1",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("user".to_string()))?;
+        Ok(user)
+    }
+
+    async fn select_users_by_ids(&self, ids: &[i64]) -> Result<Vec<UserEntity>, AppError> {
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut query_builder = QueryBuilder::<Sqlite>::new(
+            "SELECT id, username, email, password, image, bio FROM user WHERE id IN (",
+        );
+        let mut separated = query_builder.separated(", ");
+        for id in ids {
+            separated.push_bind(*id);
+        }
+        separated.push_unseparated(")");
+
+        let users = query_builder
+            .build_query_as::<UserEntity>()
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(users)
+    }
+
+    async fn select_user_by_email(&self, email: String) -> Result<UserEntity, AppError> {
+        let user = sqlx::query_as::<_, UserEntity>(
+            "SELECT id, username, email, password, image, bio FROM user WHERE email = ? limit 1",
+        )
+        .bind(email)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("user".to_string()))?;
+        Ok(user)
+    }
+
+    async fn select_user_by_username(&self, username: String) -> Result<UserEntity, AppError> {
+        let user = sqlx::query_as::<_, UserEntity>(
+            "SELECT id, username, email, password, image, bio FROM user WHERE username = ? limit 1",
+        )
+        .bind(username)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("user".to_string()))?;
+        Ok(user)
+    }
+
+    async fn update_user_by_id(
+        &self,
+        id: i64,
+        update_form: UserUpdateForm,
+    ) -> Result<(), AppError> {
+        let mut fields_values = vec![];
+        if let Some(username) = update_form.username {
+            fields_values.push(("username", username));
+        }
+        if let Some(email) = update_form.email {
+            fields_values.push(("email", email));
+        }
+        if let Some(password) = update_form.password {
+            fields_values.push(("password", encrypt_password(password)));
+        }
+        if let Some(bio) = update_form.bio {
+            fields_values.push(("bio", bio));
+        }
+        if let Some(image) = update_form.image {
+            fields_values.push(("image", image));
+        }
+
+        let mut query_builder = QueryBuilder::<Sqlite>::new("UPDATE user SET updated_at = ");
+        query_builder.push_bind(now());
+        for (column, value) in fields_values {
+            query_builder.push(", ");
+            query_builder.push(column);
+            query_builder.push(" = ");
+            query_builder.push_bind(value);
+        }
+
+        query_builder.push(" WHERE id = ");
+        query_builder.push_bind(id);
+
+        let query = query_builder.build();
+        log::info!("update user sql: {:?}", query.sql());
+        let result = query.execute(&self.pool).await?;
+
+        if result.rows_affected() > 0 {
+            Ok(())
+        } else {
+            Err(AppError::NotFound("user".to_string()))
+        }
+    }
+
+    async fn select_login_failures(&self, email: &str) -> Result<LoginFailures, AppError> {
+        let failures = sqlx::query_as::<_, LoginFailures>(
+            "select failed_login_count, locked_until from user where email = ?",
+        )
+        .bind(email)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(failures.unwrap_or_default())
+    }
+
+    async fn record_login_failure(&self, email: &str) -> Result<i32, AppError> {
+        // 原子自增，并发的失败尝试不会丢计数
+        let count = sqlx::query_scalar::<_, i32>(
+            "update user set failed_login_count = failed_login_count + 1 where email = ? returning failed_login_count",
+        )
+        .bind(email)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(count.unwrap_or(0))
+    }
+
+    async fn lock_user(&self, email: &str, until: Option<NaiveDateTime>) -> Result<(), AppError> {
+        sqlx::query("update user set locked_until = coalesce(?, locked_until) where email = ?")
+            .bind(until)
+            .bind(email)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn reset_login_failures(&self, user_id: i64) -> Result<(), AppError> {
+        sqlx::query("update user set failed_login_count = 0, locked_until = null where id = ?")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FollowRepo for SqliteRepository {
+    async fn select_follow_by_user(
+        &self,
+        follower_user_id: i64,
+        followee_user_id: i64,
+    ) -> Result<bool, AppError> {
+        let result = sqlx::query_scalar::<_, i64>(
+            "select id from user_follow where follower_user_id = ? and followee_user_id = ?",
+        )
+        .bind(follower_user_id)
+        .bind(followee_user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(result.is_some())
+    }
+
+    async fn select_following_ids(
+        &self,
+        follower_user_id: i64,
+        user_ids: &[i64],
+    ) -> Result<HashSet<i64>, AppError> {
+        if user_ids.is_empty() {
+            return Ok(HashSet::new());
+        }
+        let mut query_builder = QueryBuilder::<Sqlite>::new(
+            "select followee_user_id from user_follow where follower_user_id = ",
+        );
+        query_builder.push_bind(follower_user_id);
+        query_builder.push(" and followee_user_id in (");
+        let mut separated = query_builder.separated(", ");
+        for id in user_ids {
+            separated.push_bind(*id);
+        }
+        separated.push_unseparated(")");
+
+        let ids = query_builder
+            .build_query_scalar::<i64>()
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(ids.into_iter().collect())
+    }
+
+    async fn insert_follow_by_user(
+        &self,
+        user_id: i64,
+        followee_user_id: i64,
+    ) -> Result<i64, AppError> {
+        let result = sqlx::query(
+            "insert into user_follow(created_at, updated_at, follower_user_id, followee_user_id) values (?, ?, ?, ?)",
+        )
+        .bind(now())
+        .bind(now())
+        .bind(user_id)
+        .bind(followee_user_id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.last_insert_rowid() > 0 {
+            Ok(result.last_insert_rowid())
+        } else {
+            Err(AppError::Internal("insert follow failed".to_string()))
+        }
+    }
+
+    async fn delete_follow_by_user(
+        &self,
+        user_id: i64,
+        followee_user_id: i64,
+    ) -> Result<(), AppError> {
+        let result = sqlx::query(
+            "delete from user_follow where follower_user_id = ? and followee_user_id = ?",
+        )
+        .bind(user_id)
+        .bind(followee_user_id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() > 0 {
+            Ok(())
+        } else {
+            Err(AppError::NotFound("follow".to_string()))
+        }
+    }
+}
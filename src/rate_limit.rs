@@ -0,0 +1,433 @@
+//! Rate limiting with token buckets.
+//!
+//! Each key (a user, a client IP, an email, ..) has a bucket holding up to
+//! `limit` tokens that refills continuously over a minute. A request takes
+//! one token and is refused while the bucket is empty. Buckets are kept in a
+//! [`RateLimitStore`]; the default [`MemoryStore`] lives in this process
+//! only, so every server instance counts on its own.
+
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{HeaderMap, HeaderName, HeaderValue};
+use actix_web::http::Method;
+use actix_web::{web, HttpMessage, HttpRequest, ResponseError};
+use async_trait::async_trait;
+use futures::future::{ready, LocalBoxFuture, Ready};
+
+use crate::config::{Config, LoginConfig, RateLimitConfig};
+use crate::error::AppError;
+use crate::SessionState;
+
+/// Buckets refill completely over this window.
+const WINDOW_SECS: u64 = 60;
+/// Most keys tracked at once; the least recently used bucket makes room for a
+/// new key.
+const MAX_TRACKED_KEYS: usize = 10_000;
+
+/// A bucket after a request was counted against it.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitStatus {
+    pub limit: u32,
+    /// Requests left before the client is refused.
+    pub remaining: u32,
+    /// Until the bucket is full again.
+    pub reset: Duration,
+    /// Set when the request was refused: until the next token.
+    pub retry_after: Option<Duration>,
+}
+
+/// Where buckets are kept. A store shared between server instances can
+/// implement this to limit clients across all of them.
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    /// Takes a token from `key`'s bucket of `limit` tokens per minute.
+    async fn hit(&self, key: &str, limit: u32) -> Result<RateLimitStatus, AppError>;
+}
+
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    buckets: Mutex<Buckets>,
+}
+
+#[derive(Debug, Default)]
+struct Buckets {
+    by_key: HashMap<String, Bucket>,
+    /// Keys by the tick they were last used at, least recent first.
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    limit: u32,
+    tokens: f64,
+    updated_at: Instant,
+    /// Tick of the last request, the bucket's key into `recency`.
+    last_used: u64,
+}
+
+impl Bucket {
+    /// Tokens added per second.
+    fn refill_rate(&self) -> f64 {
+        f64::from(self.limit) / WINDOW_SECS as f64
+    }
+
+    fn refilled(self, now: Instant) -> Bucket {
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+        Bucket {
+            tokens: (self.tokens + elapsed * self.refill_rate()).min(f64::from(self.limit)),
+            updated_at: now,
+            ..self
+        }
+    }
+}
+
+impl Buckets {
+    /// `key`'s bucket, marked as the most recently used. Tracking a new key
+    /// beyond `MAX_TRACKED_KEYS` drops the least recently used bucket, so
+    /// memory and the cost of a request stay bounded however many keys
+    /// clients make up.
+    fn touch(&mut self, key: &str, limit: u32, now: Instant) -> &mut Bucket {
+        self.tick += 1;
+        if let Some(bucket) = self.by_key.get(key) {
+            self.recency.remove(&bucket.last_used);
+        } else if self.by_key.len() >= MAX_TRACKED_KEYS {
+            // 最久没用过的桶也最接近回满，丢掉它影响最小
+            if let Some((_, oldest)) = self.recency.pop_first() {
+                self.by_key.remove(&oldest);
+            }
+        }
+        self.recency.insert(self.tick, key.to_string());
+        let bucket = self.by_key.entry(key.to_string()).or_insert(Bucket {
+            limit,
+            tokens: f64::from(limit),
+            updated_at: now,
+            last_used: self.tick,
+        });
+        bucket.last_used = self.tick;
+        bucket
+    }
+}
+
+impl MemoryStore {
+    pub fn take(&self, key: &str, limit: u32) -> RateLimitStatus {
+        self.take_at(key, limit, Instant::now())
+    }
+
+    /// [`take`](Self::take) as of `now`.
+    fn take_at(&self, key: &str, limit: u32, now: Instant) -> RateLimitStatus {
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+        let bucket = buckets.touch(key, limit, now);
+        // 配置改了上限时按新上限算
+        bucket.limit = limit;
+        *bucket = bucket.refilled(now);
+
+        let retry_after = if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / bucket.refill_rate(),
+            ))
+        };
+        RateLimitStatus {
+            limit,
+            remaining: bucket.tokens as u32,
+            reset: Duration::from_secs_f64(
+                (f64::from(limit) - bucket.tokens) / bucket.refill_rate(),
+            ),
+            retry_after,
+        }
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for MemoryStore {
+    async fn hit(&self, key: &str, limit: u32) -> Result<RateLimitStatus, AppError> {
+        Ok(self.take(key, limit))
+    }
+}
+
+/// One limit over its own in-memory buckets, for checks inside handlers.
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: u32,
+    store: MemoryStore,
+}
+
+impl RateLimiter {
+    /// Allows bursts of `limit` requests and `limit` per minute sustained.
+    pub fn per_minute(limit: u32) -> Self {
+        RateLimiter {
+            limit,
+            store: MemoryStore::default(),
+        }
+    }
+
+    /// Takes a token from `key`'s bucket, or returns how long until one is
+    /// available.
+    pub fn acquire(&self, key: &str) -> Result<(), Duration> {
+        match self.store.take(key, self.limit).retry_after {
+            Some(retry_after) => Err(retry_after),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Limits for `/api/users/login` and registration.
+#[derive(Debug)]
+pub struct LoginLimiter {
+    /// Keyed by client IP; shared by login and registration.
+    pub by_ip: RateLimiter,
+    /// Keyed by the account a login resolves to, or by the lowercased email
+    /// when it names no account.
+    pub by_account: RateLimiter,
+}
+
+impl LoginLimiter {
+    pub fn new(config: &LoginConfig) -> Self {
+        LoginLimiter {
+            by_ip: RateLimiter::per_minute(config.ip_attempts_per_minute),
+            by_account: RateLimiter::per_minute(config.account_attempts_per_minute),
+        }
+    }
+}
+
+/// The client's IP: the peer address, or the one reported by the proxy
+/// when `trust_proxy` is set.
+pub fn client_ip(req: &HttpRequest, trust_proxy: bool) -> String {
+    let ip = if trust_proxy {
+        req.connection_info()
+            .realip_remote_addr()
+            .map(|addr| addr.to_string())
+    } else {
+        req.peer_addr().map(|addr| addr.ip().to_string())
+    };
+    ip.unwrap_or_else(|| "unknown".to_string())
+}
+
+/// The limits [`RateLimit`] applies, registered as app data. Without it the
+/// middleware lets every request through.
+pub struct RateLimits {
+    config: RateLimitConfig,
+    trust_proxy: bool,
+    store: Arc<dyn RateLimitStore>,
+}
+
+impl RateLimits {
+    pub fn new(config: &Config, store: Arc<dyn RateLimitStore>) -> Self {
+        RateLimits {
+            config: config.rate_limit.clone(),
+            trust_proxy: config.server.trust_proxy,
+            store,
+        }
+    }
+
+    /// The bucket a request to `scope` counts against and its limit, `None`
+    /// when it is not limited. Reads share one bucket across scopes.
+    fn bucket(&self, scope: &'static str, method: &Method) -> Option<(&'static str, u32)> {
+        if !self.config.enabled || method == Method::OPTIONS {
+            return None;
+        }
+        if method == Method::GET || method == Method::HEAD {
+            return Some(("read", self.config.reads_per_minute));
+        }
+        let limit = self.config.scopes.get(scope).copied();
+        Some((scope, limit.unwrap_or(self.config.writes_per_minute)))
+    }
+}
+
+/// Middleware limiting each client's requests to a route scope, e.g.
+/// `web::scope("/api/articles").wrap(RateLimit::scope("articles"))`.
+///
+/// Clients are told their quota in `RateLimit-Limit`, `RateLimit-Remaining`
+/// and `RateLimit-Reset` (seconds until the bucket is full) and are refused
+/// with 429 and `Retry-After` once it runs out.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    scope: &'static str,
+}
+
+impl RateLimit {
+    pub fn scope(scope: &'static str) -> Self {
+        RateLimit { scope }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = RateLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service: Rc::new(service),
+            scope: self.scope,
+        }))
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: Rc<S>,
+    scope: &'static str,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let scope = self.scope;
+        Box::pin(async move {
+            let limits = match req.app_data::<web::Data<RateLimits>>() {
+                Some(limits) => limits.clone(),
+                None => return service.call(req).await.map(|res| res.map_into_left_body()),
+            };
+            let (bucket, limit) = match limits.bucket(scope, req.method()) {
+                Some(bucket) => bucket,
+                None => return service.call(req).await.map(|res| res.map_into_left_body()),
+            };
+
+            let client = match SessionState::authenticate(req.request()).await {
+                // 认证结果放进 extensions，处理函数里的 SessionState 不用再查一次会话
+                Ok(Some(session_state)) => {
+                    let user_id = session_state.user_id;
+                    req.extensions_mut().insert(session_state);
+                    format!("user:{}", user_id)
+                }
+                // 令牌无效的请求按 IP 计数，401 留给处理函数返回
+                _ => format!("ip:{}", client_ip(req.request(), limits.trust_proxy)),
+            };
+
+            let key = format!("{}:{}", bucket, client);
+            let status = match limits.store.hit(&key, limit).await {
+                Ok(status) => status,
+                Err(e) => {
+                    // 计数存储出错时放行，不因限流把整个服务拖垮
+                    log::error!("rate limit store failed: {}", e);
+                    return service.call(req).await.map(|res| res.map_into_left_body());
+                }
+            };
+
+            if let Some(retry_after) = status.retry_after {
+                let mut response = AppError::TooManyRequests(retry_after).error_response();
+                insert_headers(response.headers_mut(), &status);
+                return Ok(req.into_response(response).map_into_right_body());
+            }
+            let mut res = service.call(req).await?;
+            insert_headers(res.headers_mut(), &status);
+            Ok(res.map_into_left_body())
+        })
+    }
+}
+
+fn insert_headers(headers: &mut HeaderMap, status: &RateLimitStatus) {
+    let reset = status.reset.as_secs() + u64::from(status.reset.subsec_nanos() > 0);
+    let values = [
+        ("ratelimit-limit", u64::from(status.limit)),
+        ("ratelimit-remaining", u64::from(status.remaining)),
+        ("ratelimit-reset", reset),
+    ];
+    for (name, value) in values {
+        headers.insert(HeaderName::from_static(name), HeaderValue::from(value));
+    }
+    headers.insert(
+        HeaderName::from_static("ratelimit-policy"),
+        HeaderValue::from_str(&format!("{};w={}", status.limit, WINDOW_SECS))
+            .expect("policy is ASCII"),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 每分钟 60 个即每秒回一个，时间和令牌数都能精确比较
+    const LIMIT: u32 = 60;
+
+    #[test]
+    fn a_full_bucket_allows_a_burst_then_refuses() {
+        let store = MemoryStore::default();
+        let start = Instant::now();
+        for expected in (0..LIMIT).rev() {
+            let status = store.take_at("key", LIMIT, start);
+            assert_eq!(status.remaining, expected);
+            assert_eq!(status.retry_after, None);
+        }
+
+        let status = store.take_at("key", LIMIT, start);
+        assert_eq!(status.remaining, 0);
+        assert_eq!(status.retry_after, Some(Duration::from_secs(1)));
+        assert_eq!(status.reset, Duration::from_secs(60));
+    }
+
+    #[test]
+    fn tokens_refill_continuously_up_to_the_limit() {
+        let store = MemoryStore::default();
+        let start = Instant::now();
+        for _ in 0..LIMIT {
+            store.take_at("key", LIMIT, start);
+        }
+
+        let later = start + Duration::from_millis(1500);
+        assert_eq!(store.take_at("key", LIMIT, later).retry_after, None);
+        let status = store.take_at("key", LIMIT, later);
+        assert_eq!(status.retry_after, Some(Duration::from_millis(500)));
+
+        // 空闲再久也只回满到上限
+        let status = store.take_at("key", LIMIT, later + Duration::from_secs(3600));
+        assert_eq!(status.remaining, LIMIT - 1);
+        assert_eq!(status.reset, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn keys_have_their_own_buckets() {
+        let store = MemoryStore::default();
+        let start = Instant::now();
+        for _ in 0..LIMIT {
+            store.take_at("a", LIMIT, start);
+        }
+        assert!(store.take_at("a", LIMIT, start).retry_after.is_some());
+        assert_eq!(store.take_at("b", LIMIT, start).remaining, LIMIT - 1);
+    }
+
+    #[test]
+    fn the_least_recently_used_key_makes_room_at_the_cap() {
+        let store = MemoryStore::default();
+        let start = Instant::now();
+        store.take_at("kept", LIMIT, start);
+        for i in 1..MAX_TRACKED_KEYS {
+            store.take_at(&format!("key-{}", i), LIMIT, start);
+        }
+        // 再用一次，"kept" 不再是最久没用的
+        store.take_at("kept", LIMIT, start);
+
+        store.take_at("new", LIMIT, start);
+        let buckets = store.buckets.lock().unwrap();
+        assert_eq!(buckets.by_key.len(), MAX_TRACKED_KEYS);
+        assert_eq!(buckets.recency.len(), MAX_TRACKED_KEYS);
+        assert!(!buckets.by_key.contains_key("key-1"));
+        assert!(buckets.by_key.contains_key("key-2"));
+        assert_eq!(buckets.by_key["kept"].tokens, f64::from(LIMIT - 2));
+        assert!(buckets.by_key.contains_key("new"));
+    }
+}
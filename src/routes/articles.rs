@@ -1,73 +1,95 @@
 use crate::models::article::{
-    ArticleCreateForm, ArticleEntity, ArticleQuery, ArticleResponse, ArticleUpdateForm,
-    ArticleWrapper, ArticlesWrapper,
+    ArticleCreateForm, ArticleEntity, ArticleHighlight, ArticleQuery, ArticleResponse,
+    ArticleSearchQuery, ArticleSearchResponse, ArticleUpdateForm, ArticleWrapper, ArticlesWrapper,
 };
-use crate::models::user::{to_author, UserEntity};
-use crate::persistence::article::{
-    delete_article_by_slug, delete_article_favorite, insert_article, insert_article_favorite,
-    select_article_by_id, select_article_by_slug, select_article_favorite,
-    select_articles_by_query, update_article_by_slug,
-};
-use crate::persistence::tag::delete_tag_by_article_id;
-use crate::persistence::user::select_user_by_id;
+use crate::models::to_profile_response;
+use crate::models::user::UserEntity;
+use crate::persistence::Repository;
+use std::collections::{HashMap, HashSet};
 
-use actix_web::{delete, get, post, put, web, HttpResponse, Responder};
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
 
-use realworld_rust_actix_web::SessionState;
-use sqlx::MySqlPool;
+use crate::authorization::ensure_article_author;
+use crate::error::AppError;
+use crate::routes::resolve_article;
+use crate::search;
+use crate::validation::ValidatedJson;
+use crate::{MaybeAuthenticated, SessionState};
 
 //
 #[get("")]
 pub async fn list_articles(
-    pool: web::Data<MySqlPool>,
-    query: web::Query<ArticleQuery>,
+    viewer: MaybeAuthenticated,
+    repo: web::Data<dyn Repository>,
+    mut query: ArticleQuery,
 ) -> actix_web::Result<impl Responder> {
     log::info!("list_articles query = {:?}", query);
+    let viewer_id = viewer.user_id();
 
-    let query = query.into_inner();
-
-    let articles = select_articles_by_query(&pool, query).await?;
-    // let user = select_user_by_id(&pool, user_id).await?;
+    query.viewer_id = viewer_id;
 
-    log::info!("articles = {:?}", articles);
-
-    let mut result_articles = vec![];
-    for a in articles {
-        // log::info!("article = {:?}", a);
-        let user = select_user_by_id(&pool, a.user_id).await?;
-        let favorited = select_article_favorite(&pool, Some(a.user_id), a.id).await?;
-
-        result_articles.push(to_article_response(a, user, favorited))
-    }
+    let page = repo.select_articles_by_query(query).await?;
+    let result_articles = to_article_responses(repo.get_ref(), page.articles, viewer_id).await?;
 
     Ok(web::Json(ArticlesWrapper::<ArticleResponse> {
         articles: result_articles,
-        articles_count: 0,
+        articles_count: page.articles_count,
+        next_cursor: page.next_cursor,
+        prev_cursor: page.prev_cursor,
     }))
 }
 
 #[get("/feed")]
 pub async fn list_articles_feed(
     session_state: SessionState,
-    pool: web::Data<MySqlPool>,
-    query: web::Query<ArticleQuery>,
+    repo: web::Data<dyn Repository>,
+    mut query: ArticleQuery,
 ) -> actix_web::Result<impl Responder> {
     let user_id = session_state.user_id;
-    let mut query = query.into_inner();
 
+    query.viewer_id = Some(user_id);
     query.feed_user_id = Some(user_id);
-    let articles = select_articles_by_query(&pool, query).await?;
-    let mut result_articles = vec![];
-    for a in articles {
-        // log::info!("article = {:?}", a);
-        let user = select_user_by_id(&pool, a.user_id).await?;
-        let favorited = select_article_favorite(&pool, Some(user_id), a.id).await?;
-
-        result_articles.push(to_article_response(a, user, favorited))
-    }
+    let page = repo.select_articles_by_query(query).await?;
+    let result_articles =
+        to_article_responses(repo.get_ref(), page.articles, Some(user_id)).await?;
     Ok(web::Json(ArticlesWrapper::<ArticleResponse> {
         articles: result_articles,
-        articles_count: 0,
+        articles_count: page.articles_count,
+        next_cursor: page.next_cursor,
+        prev_cursor: page.prev_cursor,
+    }))
+}
+
+/// Full-text search over title, description and body, best match first. Takes
+/// the `tag`, `author` and `favorited` filters of the article list and pages
+/// by `limit`/`offset`.
+#[get("/search")]
+pub async fn search_articles(
+    viewer: MaybeAuthenticated,
+    repo: web::Data<dyn Repository>,
+    query: web::Query<ArticleSearchQuery>,
+) -> actix_web::Result<impl Responder> {
+    let terms = search::terms(&query.q);
+    if terms.is_empty() {
+        return Err(AppError::validation("q", "can't be blank").into());
+    }
+
+    let mut article_query = query.to_article_query();
+    article_query.viewer_id = viewer.user_id();
+    let page = repo.search_articles(&terms, article_query).await?;
+    let articles = to_article_responses(repo.get_ref(), page.articles, viewer.user_id()).await?;
+
+    Ok(web::Json(ArticlesWrapper {
+        articles: articles
+            .into_iter()
+            .map(|article| ArticleSearchResponse {
+                highlight: to_article_highlight(&article, &terms),
+                article,
+            })
+            .collect::<Vec<_>>(),
+        articles_count: page.articles_count,
+        next_cursor: None,
+        prev_cursor: None,
     }))
 }
 
@@ -75,23 +97,23 @@ pub async fn list_articles_feed(
 #[post("")]
 pub async fn create_article(
     session_state: SessionState,
-    pool: web::Data<MySqlPool>,
-    data: web::Json<ArticleWrapper<ArticleCreateForm>>,
+    repo: web::Data<dyn Repository>,
+    data: ValidatedJson<ArticleWrapper<ArticleCreateForm>>,
 ) -> actix_web::Result<impl Responder> {
     log::info!("create_article data = {:?}", data);
     let user_id = session_state.user_id;
     let article = data.into_inner().article;
     // let tagList = article.clone().tagList;
-    let last_insert_id = insert_article(&pool, article, user_id).await?;
-    let article = select_article_by_id(&pool, last_insert_id).await?;
-    let user = select_user_by_id(&pool, user_id).await?;
+    let last_insert_id = repo.insert_article(article, user_id).await?;
+    let article = repo.select_article_by_id(last_insert_id).await?;
+    let user = repo.select_user_by_id(user_id).await?;
 
     // let tz_offset = FixedOffset::east(8 * 3600);
     // let t = tz_offset.from_local_datetime(&article.created_at).unwrap().to_rfc3339();
     // log::info!("t = {}", t);
 
     let r = ArticleWrapper {
-        article: to_article_response(article, user, false),
+        article: to_article_response(article, user, false, false),
     };
     log::info!("create_article: r = {:?}", r);
 
@@ -101,7 +123,8 @@ pub async fn create_article(
 #[delete("/{slug}")]
 pub async fn delete_article(
     session_state: SessionState,
-    pool: web::Data<MySqlPool>,
+    repo: web::Data<dyn Repository>,
+    req: HttpRequest,
     path: web::Path<String>,
 ) -> actix_web::Result<impl Responder> {
     let user_id = session_state.user_id;
@@ -109,9 +132,9 @@ pub async fn delete_article(
     let slug2 = slug.clone();
     log::info!("delete_article: slug: {:?}", slug);
 
-    let article = select_article_by_slug(&pool, slug).await?;
-    delete_article_by_slug(&pool, user_id, slug2).await?;
-    delete_tag_by_article_id(&pool, article.id).await?;
+    let article = resolve_article(repo.get_ref(), &req, slug).await?;
+    ensure_article_author(&article, user_id)?;
+    repo.delete_article_by_slug(user_id, slug2).await?;
 
     Ok(HttpResponse::NoContent().finish())
 }
@@ -119,23 +142,27 @@ pub async fn delete_article(
 #[put("/{slug}")]
 pub async fn update_article(
     session_state: SessionState,
-    pool: web::Data<MySqlPool>,
+    repo: web::Data<dyn Repository>,
+    req: HttpRequest,
     path: web::Path<String>,
-    data: web::Json<ArticleWrapper<ArticleUpdateForm>>,
+    data: ValidatedJson<ArticleWrapper<ArticleUpdateForm>>,
 ) -> actix_web::Result<impl Responder> {
     let user_id = session_state.user_id;
     let slug = path.into_inner();
-    let slug2 = slug.clone();
     let update_form = data.into_inner().article;
-    update_article_by_slug(&pool, user_id, slug, update_form).await?;
-    let article = select_article_by_slug(&pool, slug2).await?;
 
-    let favorited = select_article_favorite(&pool, Some(user_id), article.id).await?;
+    let article = resolve_article(repo.get_ref(), &req, slug.clone()).await?;
+    ensure_article_author(&article, user_id)?;
+    repo.update_article_by_slug(user_id, slug, update_form).await?;
+    // 标题修改后 slug 会变化，按 id 重新查询
+    let article = repo.select_article_by_id(article.id as u64).await?;
+
+    let favorited = repo.select_article_favorite(Some(user_id), article.id).await?;
 
-    let user = select_user_by_id(&pool, user_id).await?;
+    let user = repo.select_user_by_id(user_id).await?;
 
     Ok(web::Json(ArticleWrapper {
-        article: to_article_response(article, user, favorited),
+        article: to_article_response(article, user, favorited, false),
     }))
 }
 
@@ -144,65 +171,111 @@ pub async fn update_article(
 //
 #[get("/{slug}")]
 pub async fn single_article(
-    // session_state: SessionState,
-    pool: web::Data<MySqlPool>,
+    viewer: MaybeAuthenticated,
+    repo: web::Data<dyn Repository>,
+    req: HttpRequest,
     path: web::Path<String>,
 ) -> actix_web::Result<impl Responder> {
     log::info!("single_article: path: {:?}", path);
-    // let user_id = session_state.user_id;
+    let viewer_id = viewer.user_id();
     let slug = path.into_inner();
-    let article = select_article_by_slug(&pool, slug).await?;
-    let user = select_user_by_id(&pool, article.user_id).await?;
-    let favorited = select_article_favorite(&pool, None, article.id).await?;
+    let article = resolve_article(repo.get_ref(), &req, slug).await?;
+    let user = repo.select_user_by_id(article.user_id).await?;
+    let favorited = repo.select_article_favorite(viewer_id, article.id).await?;
+    let following = repo.is_following(viewer_id, article.user_id).await?;
 
     Ok(web::Json(ArticleWrapper {
-        article: to_article_response(article, user, favorited),
+        article: to_article_response(article, user, favorited, following),
     }))
 }
 
 #[post("/{slug}/favorite")]
 pub async fn favorite_article(
     session_state: SessionState,
-    pool: web::Data<MySqlPool>,
+    repo: web::Data<dyn Repository>,
+    req: HttpRequest,
     path: web::Path<String>,
 ) -> actix_web::Result<impl Responder> {
     let slug = path.into_inner();
     let user_id = session_state.user_id;
 
-    let article = select_article_by_slug(&pool, slug.clone()).await?;
-    let user = select_user_by_id(&pool, user_id).await?;
-    insert_article_favorite(&pool, user_id, article.id).await?;
-    let article = select_article_by_slug(&pool, slug.clone()).await?;
+    let article = resolve_article(repo.get_ref(), &req, slug.clone()).await?;
+    let user = repo.select_user_by_id(article.user_id).await?;
+    let following = repo.is_following(Some(user_id), article.user_id).await?;
+    repo.insert_article_favorite(user_id, article.id).await?;
+    let article = repo.select_article_by_slug(slug.clone()).await?;
 
     // log::info!()
     Ok(web::Json(ArticleWrapper {
-        article: to_article_response(article, user, true),
+        article: to_article_response(article, user, true, following),
     }))
 }
 
 #[delete("/{slug}/favorite")]
 pub async fn unfavorite_article(
     session_state: SessionState,
-    pool: web::Data<MySqlPool>,
+    repo: web::Data<dyn Repository>,
+    req: HttpRequest,
     path: web::Path<String>,
 ) -> actix_web::Result<impl Responder> {
     let slug = path.into_inner();
     let user_id = session_state.user_id;
 
-    let article = select_article_by_slug(&pool, slug.clone()).await?;
-    let user = select_user_by_id(&pool, user_id).await?;
-    delete_article_favorite(&pool, user_id, article.id).await?;
-    let article = select_article_by_slug(&pool, slug.clone()).await?;
+    let article = resolve_article(repo.get_ref(), &req, slug.clone()).await?;
+    let user = repo.select_user_by_id(article.user_id).await?;
+    let following = repo.is_following(Some(user_id), article.user_id).await?;
+    repo.delete_article_favorite(user_id, article.id).await?;
+    let article = repo.select_article_by_slug(slug.clone()).await?;
 
     Ok(web::Json(ArticleWrapper {
-        article: to_article_response(article, user, false),
+        article: to_article_response(article, user, false, following),
     }))
 }
 
+/// Builds list responses with a constant number of queries: one for the
+/// authors, and for a logged-in viewer one each for favorites and follows.
+async fn to_article_responses(
+    repo: &dyn Repository,
+    articles: Vec<ArticleEntity>,
+    viewer_id: Option<i64>,
+) -> Result<Vec<ArticleResponse>, AppError> {
+    let mut author_ids: Vec<i64> = articles.iter().map(|a| a.user_id).collect();
+    author_ids.sort_unstable();
+    author_ids.dedup();
+    let article_ids: Vec<i64> = articles.iter().map(|a| a.id).collect();
+
+    let authors: HashMap<i64, UserEntity> = repo
+        .select_users_by_ids(&author_ids)
+        .await?
+        .into_iter()
+        .map(|u| (u.id, u))
+        .collect();
+    let (favorited, following) = match viewer_id {
+        Some(viewer_id) => (
+            repo.select_favorited_article_ids(viewer_id, &article_ids).await?,
+            repo.select_following_ids(viewer_id, &author_ids).await?,
+        ),
+        None => (HashSet::new(), HashSet::new()),
+    };
+
+    let mut result_articles = Vec::with_capacity(articles.len());
+    for a in articles {
+        let user = authors
+            .get(&a.user_id)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound("user".to_string()))?;
+        let is_favorited = favorited.contains(&a.id);
+        let author_followed = following.contains(&a.user_id);
+        result_articles.push(to_article_response(a, user, is_favorited, author_followed));
+    }
+    Ok(result_articles)
+}
+
 fn to_article_response(
     article: ArticleEntity,
     user: UserEntity,
     favorited: bool,
+    following: bool,
 ) -> ArticleResponse {
     let mut tag_list = serde_json::from_str(&article.tag_list).unwrap_or(Vec::<String>::new());
     tag_list.sort();
@@ -222,6 +295,14 @@ fn to_article_response(
         favorites_count: article.favorites_count,
         favorited,
         tag_list,
-        author: to_author(user),
+        author: to_profile_response(user, following),
+    }
+}
+
+fn to_article_highlight(article: &ArticleResponse, terms: &[String]) -> ArticleHighlight {
+    ArticleHighlight {
+        title: search::highlight(&article.title, terms),
+        description: search::highlight(&article.description, terms),
+        body: search::snippet(&article.body, terms),
     }
 }
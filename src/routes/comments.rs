@@ -3,37 +3,54 @@ use crate::{
         comment::{
             CommentCreateForm, CommentEntity, CommentResponse, CommentWrapper, CommentsWrapper,
         },
-        user::to_author,
+        to_profile_response,
         user::UserEntity,
     },
-    persistence::{
-        article::select_article_by_slug,
-        comment::{
-            delete_comment_by_id, get_comment_by_id, insert_article_comment,
-            select_comments_by_article_id,
-        },
-        user::select_user_by_id,
-    },
+    persistence::Repository,
 };
-use actix_web::{delete, get, post, web, HttpResponse, Responder};
-use realworld_rust_actix_web::SessionState;
-use sqlx::MySqlPool;
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Responder};
+use std::collections::{HashMap, HashSet};
+use crate::authorization::ensure_can_delete_comment;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::routes::resolve_article;
+use crate::validation::ValidatedJson;
+use crate::{MaybeAuthenticated, SessionState};
 
 #[get("/{slug}/comments")]
 pub async fn get_article_comments(
-    // session_state: SessionState,
-    pool: web::Data<MySqlPool>,
+    viewer: MaybeAuthenticated,
+    repo: web::Data<dyn Repository>,
+    req: HttpRequest,
     path: web::Path<String>,
 ) -> actix_web::Result<impl Responder> {
     let slug = path.into_inner();
-    // let user_id = session_state.user_id;
-    let article = select_article_by_slug(&pool, slug).await?;
-    let comments = select_comments_by_article_id(&pool, article.id).await?;
+    let viewer_id = viewer.user_id();
+    let article = resolve_article(repo.get_ref(), &req, slug).await?;
+    let comments = repo.select_comments_by_article_id(article.id).await?;
+
+    // 评论作者与关注关系各一次批量查询，避免逐条查询
+    let mut author_ids: Vec<i64> = comments.iter().map(|c| c.user_id).collect();
+    author_ids.sort_unstable();
+    author_ids.dedup();
+    let authors: HashMap<i64, UserEntity> = repo.select_users_by_ids(&author_ids)
+        .await?
+        .into_iter()
+        .map(|u| (u.id, u))
+        .collect();
+    let following = match viewer_id {
+        Some(viewer_id) => repo.select_following_ids(viewer_id, &author_ids).await?,
+        None => HashSet::new(),
+    };
 
     let mut result_comments = vec![];
     for comment in comments {
-        let user = select_user_by_id(&pool, comment.user_id).await?;
-        let comment = to_comment_response(comment, user);
+        let user = authors
+            .get(&comment.user_id)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound("user".to_string()))?;
+        let author_followed = following.contains(&comment.user_id);
+        let comment = to_comment_response(comment, user, author_followed);
         result_comments.push(comment);
     }
     Ok(web::Json(CommentsWrapper {
@@ -44,39 +61,47 @@ pub async fn get_article_comments(
 #[post("/{slug}/comments")]
 pub async fn create_article_comments(
     session_state: SessionState,
-    pool: web::Data<MySqlPool>,
+    repo: web::Data<dyn Repository>,
+    req: HttpRequest,
     path: web::Path<String>,
-    data: web::Json<CommentWrapper<CommentCreateForm>>,
+    data: ValidatedJson<CommentWrapper<CommentCreateForm>>,
 ) -> actix_web::Result<impl Responder> {
     let slug = path.into_inner();
     let user_id = session_state.user_id;
     let comment_form = data.into_inner().comment;
-    let article = select_article_by_slug(&pool, slug).await?;
+    let article = resolve_article(repo.get_ref(), &req, slug).await?;
 
-    let comment_id = insert_article_comment(&pool, user_id, comment_form.body, article.id).await?;
-    let comment = get_comment_by_id(&pool, comment_id).await?;
-    let user = select_user_by_id(&pool, user_id).await?;
-    let comment = to_comment_response(comment, user);
+    let comment_id = repo.insert_article_comment(user_id, comment_form.body, article.id).await?;
+    let comment = repo.get_comment_by_id(comment_id).await?;
+    let user = repo.select_user_by_id(user_id).await?;
+    let comment = to_comment_response(comment, user, false);
     Ok(web::Json(CommentWrapper { comment }))
 }
 
 #[delete("/{slug}/comments/{id}")]
 pub async fn delete_article_comment(
-    // session_state: SessionState,
-    pool: web::Data<MySqlPool>,
+    session_state: SessionState,
+    repo: web::Data<dyn Repository>,
+    req: HttpRequest,
+    config: web::Data<Config>,
     path: web::Path<(String, i64)>,
 ) -> actix_web::Result<impl Responder> {
-    // let user_id = session_state.user_id;
-    let (_slug, comment_id) = path.into_inner();
+    let user_id = session_state.user_id;
+    let (slug, comment_id) = path.into_inner();
 
-    // let article = select_article_by_slug(&pool, slug).await?;
-    // let article_id = article.id;
+    let article = resolve_article(repo.get_ref(), &req, slug).await?;
+    let comment = repo.get_comment_by_id(comment_id).await?;
+    ensure_can_delete_comment(&config.policy, &article, &comment, user_id)?;
 
-    delete_comment_by_id(&pool, comment_id).await?;
+    repo.delete_comment_by_id(comment_id).await?;
     Ok(HttpResponse::NoContent().finish())
 }
 
-fn to_comment_response(comment: CommentEntity, user: UserEntity) -> CommentResponse {
+fn to_comment_response(
+    comment: CommentEntity,
+    user: UserEntity,
+    following: bool,
+) -> CommentResponse {
     CommentResponse {
         id: comment.id,
         body: comment.body,
@@ -88,6 +113,6 @@ fn to_comment_response(comment: CommentEntity, user: UserEntity) -> CommentRespo
             .updated_at
             .format("%Y-%m-%dT%H:%M:%S%.3fZ")
             .to_string(),
-        author: to_author(user),
+        author: to_profile_response(user, following),
     }
 }
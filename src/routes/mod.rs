@@ -1,8 +1,102 @@
+use actix_web::http::Method;
+use actix_web::{web, HttpRequest};
 
+use crate::error::AppError;
+use crate::models::article::ArticleEntity;
+use crate::persistence::Repository;
+use crate::rate_limit::RateLimit;
 
 pub mod users;
 pub mod articles;
 pub mod profiles;
 pub mod tags;
 pub mod comments;
+pub mod well_known;
 
+/// Registers every API scope. Shared by `main` and by tests that build the
+/// app around another `Repository`.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        // 不需要登录的服务
+        web::scope("/api/users")
+            .wrap(RateLimit::scope("users"))
+            .service(users::login_user)
+            .service(users::refresh_session)
+            .service(users::registry_user),
+    )
+    .service(
+        web::scope("/api/articles")
+            .wrap(RateLimit::scope("articles"))
+            .service(articles::list_articles)
+            .service(articles::create_article)
+            .service(articles::delete_article)
+            .service(articles::update_article)
+            .service(articles::list_articles_feed)
+            .service(articles::search_articles)
+            .service(articles::single_article)
+            .service(comments::get_article_comments)
+            .service(comments::create_article_comments)
+            .service(comments::delete_article_comment)
+            .service(articles::favorite_article)
+            .service(articles::unfavorite_article),
+    )
+    .service(
+        web::scope("/api/user")
+            .wrap(RateLimit::scope("user"))
+            .service(users::current_user)
+            .service(users::update_user)
+            .service(users::logout_user)
+            .service(users::logout_all_sessions),
+    )
+    .service(
+        web::scope("/api/profiles")
+            .wrap(RateLimit::scope("profiles"))
+            .service(profiles::follow_user)
+            .service(profiles::delete_follow_user)
+            .service(profiles::get_profile),
+    )
+    .service(
+        web::scope("/api/tags")
+            .wrap(RateLimit::scope("tags"))
+            .service(tags::suggest_tags)
+            .service(tags::all_tags),
+    )
+    .service(web::scope("/.well-known").service(well_known::jwks));
+}
+
+/// Loads the article at `slug` for a `{slug}` route. A former slug is answered
+/// with a permanent redirect to the same URL under the current slug: 301 for
+/// GET/HEAD, 308 otherwise so clients replay the method and body.
+pub(crate) async fn resolve_article(
+    repo: &dyn Repository,
+    req: &HttpRequest,
+    slug: String,
+) -> Result<ArticleEntity, AppError> {
+    match repo.select_article_by_slug(slug.clone()).await {
+        Err(AppError::NotFound(resource)) => {
+            let current = match repo.select_current_slug(slug.clone()).await? {
+                Some(current) => current,
+                None => return Err(AppError::NotFound(resource)),
+            };
+            let location = redirect_location(req, &slug, &current);
+            if req.method() == Method::GET || req.method() == Method::HEAD {
+                Err(AppError::MovedPermanently(location))
+            } else {
+                Err(AppError::PermanentRedirect(location))
+            }
+        }
+        result => result,
+    }
+}
+
+fn redirect_location(req: &HttpRequest, old_slug: &str, current_slug: &str) -> String {
+    let path = req.path().replacen(
+        &format!("/articles/{}", old_slug),
+        &format!("/articles/{}", current_slug),
+        1,
+    );
+    match req.query_string() {
+        "" => path,
+        query => format!("{}?{}", path, query),
+    }
+}
@@ -1,23 +1,19 @@
 use actix_web::{delete, get, post, web, Responder};
-use realworld_rust_actix_web::SessionState;
-use sqlx::MySqlPool;
+use crate::{MaybeAuthenticated, SessionState};
 
-use crate::models::{to_profile_response, ProfileResponse, ProfileWrapper};
-use crate::persistence::user::{delete_follow_by_user, insert_follow_by_user};
-use crate::persistence::user::{select_follow_by_user, select_user_by_username};
+use crate::models::{to_profile_response, ProfileWrapper};
+use crate::persistence::Repository;
 
 #[get("/{username}")]
 pub async fn get_profile(
-    session_state: SessionState,
+    viewer: MaybeAuthenticated,
     path: web::Path<String>,
-    pool: web::Data<MySqlPool>,
+    repo: web::Data<dyn Repository>,
 ) -> actix_web::Result<impl Responder> {
-    let user_id = session_state.user_id;
-
     let username = path.into_inner();
 
-    let target_user = select_user_by_username(&pool, username).await?;
-    let following = select_follow_by_user(&pool, user_id, target_user.id).await?;
+    let target_user = repo.select_user_by_username(username).await?;
+    let following = repo.is_following(viewer.user_id(), target_user.id).await?;
 
     Ok(web::Json(ProfileWrapper {
         profile: to_profile_response(target_user, following),
@@ -28,14 +24,14 @@ pub async fn get_profile(
 pub async fn follow_user(
     session_state: SessionState,
     path: web::Path<String>,
-    pool: web::Data<MySqlPool>,
+    repo: web::Data<dyn Repository>,
 ) -> actix_web::Result<impl Responder> {
     let user_id = session_state.user_id;
 
     let username = path.into_inner();
 
-    let target_user = select_user_by_username(&pool, username).await?;
-    let _last_insert_id = insert_follow_by_user(&pool, user_id, target_user.id).await?;
+    let target_user = repo.select_user_by_username(username).await?;
+    let _last_insert_id = repo.insert_follow_by_user(user_id, target_user.id).await?;
 
     Ok(web::Json(ProfileWrapper {
         profile: to_profile_response(target_user, true),
@@ -46,15 +42,15 @@ pub async fn follow_user(
 pub async fn delete_follow_user(
     session_state: SessionState,
     path: web::Path<String>,
-    pool: web::Data<MySqlPool>,
+    repo: web::Data<dyn Repository>,
 ) -> actix_web::Result<impl Responder> {
     let user_id = session_state.user_id;
 
     let username = path.into_inner();
 
-    let target_user = select_user_by_username(&pool, username).await?;
+    let target_user = repo.select_user_by_username(username).await?;
 
-    delete_follow_by_user(&pool, user_id, target_user.id).await?;
+    repo.delete_follow_by_user(user_id, target_user.id).await?;
 
     Ok(web::Json(ProfileWrapper {
         profile: to_profile_response(target_user, false),
@@ -1,9 +1,40 @@
-use crate::{models::TagsWrapper, persistence::tag::select_all_tag};
+use crate::{
+    models::{TagQuery, TagSuggestQuery, TagsWrapper},
+    persistence::Repository,
+    validation::normalize_tag,
+};
 use actix_web::{get, web, Responder};
-use sqlx::MySqlPool;
 
+const DEFAULT_SUGGESTIONS: i32 = 10;
+const MAX_SUGGESTIONS: i32 = 50;
+
+/// Tags in use, most popular first. `?prefix=ru` narrows to tags starting
+/// with `ru`, `?limit=10` returns at most ten.
 #[get("")]
-pub async fn all_tags(pool: web::Data<MySqlPool>) -> actix_web::Result<impl Responder> {
-    let tags = select_all_tag(&pool).await?;
+pub async fn all_tags(
+    repo: web::Data<dyn Repository>,
+    query: web::Query<TagQuery>,
+) -> actix_web::Result<impl Responder> {
+    let mut query = query.into_inner();
+    // 标签按小写存储，前缀也按同样规则处理
+    query.prefix = query.prefix.as_deref().map(normalize_tag);
+    let tags = repo.select_popular_tags(query).await?;
+    Ok(web::Json(TagsWrapper { tags }))
+}
+
+/// Autocomplete for the tag editor: tags starting with `q` in any case,
+/// most popular first. `limit` defaults to 10 and is capped at 50.
+#[get("/suggest")]
+pub async fn suggest_tags(
+    repo: web::Data<dyn Repository>,
+    query: web::Query<TagSuggestQuery>,
+) -> actix_web::Result<impl Responder> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_SUGGESTIONS)
+        .clamp(1, MAX_SUGGESTIONS);
+    let tags = repo
+        .select_tags_by_prefix(query.q.trim(), limit as i64)
+        .await?;
     Ok(web::Json(TagsWrapper { tags }))
 }
@@ -1,22 +1,36 @@
+use crate::models::session::{RefreshForm, SessionDevice};
 use crate::models::user::{
-    UserEntity, UserLogin, UserRegistryForm, UserResponse, UserUpdateForm, UserWrapper,
+    LoginFailures, UserEntity, UserLogin, UserRegistryForm, UserResponse, UserUpdateForm,
+    UserWrapper,
 };
 use crate::models::Claims;
-use crate::persistence::user::{
-    insert_user, select_user_by_email, select_user_by_id, update_user_by_id,
-};
-use crate::utils::verify_password;
-use actix_web::{error, get, post, put, web, Responder};
-use jsonwebtoken::{EncodingKey, Header};
-use realworld_rust_actix_web::SessionState;
-use sqlx::MySqlPool;
+use crate::persistence::Repository;
+use crate::rate_limit::{client_ip, LoginLimiter};
+use crate::utils::{dummy_password_hash, generate_token, hash_token, verify_password};
+use actix_web::cookie::time::Duration as CookieDuration;
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::http::header;
+use actix_web::{get, post, put, web, HttpRequest, HttpResponse, Responder};
+use chrono::{TimeDelta, Utc};
+use crate::config::Config;
+use crate::error::AppError;
+use crate::jwt::JwtKeys;
+use crate::validation::ValidatedJson;
+use crate::{SessionState, ACCESS_TOKEN_COOKIE};
 use std::ops::Add;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+/// Longest user agent stored with a session.
+const MAX_USER_AGENT_LENGTH: usize = 512;
+
 #[post("")]
 pub async fn registry_user(
-    json: web::Json<UserWrapper<UserRegistryForm>>,
-    pool: web::Data<MySqlPool>,
+    req: HttpRequest,
+    json: ValidatedJson<UserWrapper<UserRegistryForm>>,
+    repo: web::Data<dyn Repository>,
+    config: web::Data<Config>,
+    keys: web::Data<JwtKeys>,
+    limiter: web::Data<LoginLimiter>,
 ) -> actix_web::Result<impl Responder> {
     let UserRegistryForm {
         username,
@@ -24,82 +38,183 @@ pub async fn registry_user(
         password,
     } = json.into_inner().user;
 
-    let last_insert_id = insert_user(&pool, username, email, password).await?;
-    let user = select_user_by_id(&pool, last_insert_id as i64).await?;
+    let ip = client_ip(&req, config.server.trust_proxy);
+    limiter
+        .by_ip
+        .acquire(&ip)
+        .map_err(AppError::TooManyRequests)?;
 
-    // 创建 JWT 的 payload
-    let my_claims = Claims {
-        sub: user.id,
-        exp: SystemTime::now()
-            .add(Duration::from_secs(60 * 60 * 2))
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs(),
-    };
+    let last_insert_id = repo.insert_user(username, email, password).await?;
+    let user = repo.select_user_by_id(last_insert_id as i64).await?;
 
-    // 生成 JWT
-    let token = jsonwebtoken::encode(
-        &Header::default(),
-        &my_claims,
-        &EncodingKey::from_secret("realworld".as_ref()),
-    )
-    .unwrap();
+    let (token, refresh_token) =
+        start_session(&req, repo.get_ref(), &config, &keys, user.id).await?;
 
-    Ok(web::Json(UserWrapper {
-        user: to_user_response(user, Some(token)),
-    }))
+    let mut user = to_user_response(user, Some(token));
+    user.refresh_token = Some(refresh_token);
+    Ok(session_response(&config, user))
 }
 
 #[post("/login")]
 pub async fn login_user(
-    json: web::Json<UserWrapper<UserLogin>>,
-    pool: web::Data<MySqlPool>,
+    req: HttpRequest,
+    json: ValidatedJson<UserWrapper<UserLogin>>,
+    repo: web::Data<dyn Repository>,
+    config: web::Data<Config>,
+    keys: web::Data<JwtKeys>,
+    limiter: web::Data<LoginLimiter>,
 ) -> actix_web::Result<impl Responder> {
     // println!("login_user: {:?}", json);
     // let email = json.email;
     // let password = json.password;
     let UserLogin { email, password } = json.into_inner().user;
 
-    let user = select_user_by_email(&pool, email).await?;
+    let ip = client_ip(&req, config.server.trust_proxy);
+    limiter
+        .by_ip
+        .acquire(&ip)
+        .map_err(AppError::TooManyRequests)?;
 
-    // log::info!("login_user: {:?}", user);
+    // 未知邮箱、密码错误、账号锁定都返回同一个 401，并且都做一次 bcrypt、
+    // 按邮箱读写同样的失败计数，从响应内容和耗时上都看不出邮箱是否注册过
+    let invalid = || AppError::Unauthorized("invalid email or password".to_string());
+    let user = match repo.select_user_by_email(email.clone()).await {
+        Ok(user) => Some(user),
+        Err(AppError::NotFound(_)) => None,
+        Err(e) => return Err(e.into()),
+    };
+    // 按查到的账号限流，邮箱大小写是否算同一账号由后端决定，和失败计数一致
+    let account = match &user {
+        Some(user) => format!("user:{}", user.id),
+        None => format!("email:{}", email.to_lowercase()),
+    };
+    limiter
+        .by_account
+        .acquire(&account)
+        .map_err(AppError::TooManyRequests)?;
+    let hash = user
+        .as_ref()
+        .map_or(dummy_password_hash(), |user| user.password.as_str());
+    let verified = verify_password(password, hash);
 
-    // 创建 JWT 的 payload
-    let my_claims = Claims {
-        sub: user.id,
-        exp: SystemTime::now()
-            .add(Duration::from_secs(60 * 60 * 2))
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs(),
+    let now = Utc::now().naive_utc();
+    let failures = repo.select_login_failures(&email).await?;
+    let user = match user {
+        Some(user) if verified && !failures.is_locked(now) => user,
+        user => {
+            if let Some(user) = user.as_ref().filter(|_| failures.is_locked(now)) {
+                log::warn!("login refused, user {} is locked", user.id);
+            }
+            // 锁定期间的尝试同样计数，持续尝试会让锁定时间继续翻倍
+            let count = repo.record_login_failure(&email).await?;
+            let lockout = config.login.lockout(count.max(0) as u32);
+            if let (Some(user), Some(lockout)) = (&user, lockout) {
+                log::warn!(
+                    "locking user {} for {:?} after {} failed logins",
+                    user.id,
+                    lockout,
+                    count
+                );
+            }
+            let until = lockout.map(|lockout| {
+                now + i64::try_from(lockout.as_secs())
+                    .ok()
+                    .and_then(TimeDelta::try_seconds)
+                    .expect("login.max_lockout_secs is bounded by Config::validate")
+            });
+            repo.lock_user(&email, until).await?;
+            return Err(invalid().into());
+        }
     };
+    if failures != LoginFailures::default() {
+        repo.reset_login_failures(user.id).await?;
+    }
+
+    let (token, refresh_token) =
+        start_session(&req, repo.get_ref(), &config, &keys, user.id).await?;
+    let mut user = to_user_response(user, Some(token));
+    user.refresh_token = Some(refresh_token);
+    Ok(session_response(&config, user))
+}
 
-    // 生成 JWT
-    let token = jsonwebtoken::encode(
-        &Header::default(),
-        &my_claims,
-        &EncodingKey::from_secret("realworld".as_ref()),
-    )
-    .unwrap();
-    if verify_password(password, &user.password) {
-        Ok(web::Json(UserWrapper {
-            user: to_user_response(user, Some(token)),
-        }))
-    } else {
-        log::error!("invalid email or password");
-        Err(error::ErrorUnauthorized("invalid email or password"))
+/// Trades a refresh token for a new access token. The refresh token is
+/// rotated: the one presented stops working and a new one is returned.
+#[post("/refresh")]
+pub async fn refresh_session(
+    json: ValidatedJson<UserWrapper<RefreshForm>>,
+    repo: web::Data<dyn Repository>,
+    config: web::Data<Config>,
+    keys: web::Data<JwtKeys>,
+) -> actix_web::Result<impl Responder> {
+    let RefreshForm { refresh_token } = json.into_inner().user;
+    let invalid = || AppError::Unauthorized("invalid refresh token".to_string());
+
+    let old_hash = hash_token(&refresh_token);
+    let session = match repo.select_session_by_refresh_token(&old_hash).await {
+        Ok(session) => session,
+        Err(AppError::NotFound(_)) => return Err(invalid().into()),
+        Err(e) => return Err(e.into()),
+    };
+    if !session.is_active(Utc::now().naive_utc()) {
+        return Err(invalid().into());
     }
+
+    let refresh_token = generate_token();
+    // 旋转失败说明同一个刷新令牌已被并发使用过
+    match repo
+        .rotate_session(
+            session.id,
+            &old_hash,
+            hash_token(&refresh_token),
+            session_expires_at(&config),
+        )
+        .await
+    {
+        Ok(()) => {}
+        Err(AppError::NotFound(_)) => return Err(invalid().into()),
+        Err(e) => return Err(e.into()),
+    }
+
+    let user = repo.select_user_by_id(session.user_id).await?;
+    let token = create_token(&config, &keys, user.id, session.id)?;
+    let mut user = to_user_response(user, Some(token));
+    user.refresh_token = Some(refresh_token);
+    Ok(session_response(&config, user))
+}
+
+/// Revokes the session of the access token used; its refresh token and any
+/// access token issued for it stop working.
+#[post("/logout")]
+pub async fn logout_user(
+    session_state: SessionState,
+    repo: web::Data<dyn Repository>,
+    config: web::Data<Config>,
+) -> actix_web::Result<impl Responder> {
+    repo.revoke_session(session_state.user_id, session_state.session_id)
+        .await?;
+    Ok(logout_response(&config))
+}
+
+/// Revokes every session of the current user, this one included.
+#[post("/logout-all")]
+pub async fn logout_all_sessions(
+    session_state: SessionState,
+    repo: web::Data<dyn Repository>,
+    config: web::Data<Config>,
+) -> actix_web::Result<impl Responder> {
+    repo.revoke_user_sessions(session_state.user_id).await?;
+    Ok(logout_response(&config))
 }
 //
 #[get("")]
 pub async fn current_user(
     session_state: SessionState,
-    pool: web::Data<MySqlPool>,
+    repo: web::Data<dyn Repository>,
 ) -> actix_web::Result<impl Responder> {
     // log::info!("current_user: session_state: {:?}", session_state);
-    let SessionState { user_id, token } = session_state;
+    let SessionState { user_id, token, .. } = session_state;
 
-    let user = select_user_by_id(&pool, user_id).await?;
+    let user = repo.select_user_by_id(user_id).await?;
     Ok(web::Json(UserWrapper {
         user: to_user_response(user, Some(token)),
     }))
@@ -108,24 +223,117 @@ pub async fn current_user(
 #[put("")]
 pub async fn update_user(
     session_state: SessionState,
-    pool: web::Data<MySqlPool>,
-    json: web::Json<UserWrapper<UserUpdateForm>>,
+    repo: web::Data<dyn Repository>,
+    json: ValidatedJson<UserWrapper<UserUpdateForm>>,
 ) -> actix_web::Result<impl Responder> {
-    let SessionState { user_id, token } = session_state;
+    let SessionState { user_id, .. } = session_state;
 
-    update_user_by_id(&pool, user_id, json.into_inner().user).await?;
-    let user = select_user_by_id(&pool, user_id).await?;
+    repo.update_user_by_id(user_id, json.into_inner().user).await?;
+    let user = repo.select_user_by_id(user_id).await?;
 
     Ok(web::Json(UserWrapper {
         user: to_user_response(user, None),
     }))
 }
 
+/// Opens a session for `user_id` and returns its access and refresh tokens.
+async fn start_session(
+    req: &HttpRequest,
+    repo: &dyn Repository,
+    config: &Config,
+    keys: &JwtKeys,
+    user_id: i64,
+) -> Result<(String, String), AppError> {
+    let device = SessionDevice {
+        user_agent: req
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|ua| ua.to_str().ok())
+            .map(|ua| ua.chars().take(MAX_USER_AGENT_LENGTH).collect()),
+        ip: Some(client_ip(req, config.server.trust_proxy)),
+    };
+    let refresh_token = generate_token();
+    let session_id = repo
+        .insert_session(
+            user_id,
+            hash_token(&refresh_token),
+            device,
+            session_expires_at(config),
+        )
+        .await?;
+    let token = create_token(config, keys, user_id, session_id)?;
+    Ok((token, refresh_token))
+}
+
+/// Responds with `user`, also setting the access token cookie when
+/// `jwt.cookie` is on.
+fn session_response(config: &Config, user: UserResponse) -> HttpResponse {
+    let mut response = HttpResponse::Ok();
+    if let (true, Some(token)) = (config.jwt.cookie, &user.token) {
+        response.cookie(access_token_cookie(config, token.clone()));
+    }
+    response.json(UserWrapper { user })
+}
+
+fn logout_response(config: &Config) -> HttpResponse {
+    let mut response = HttpResponse::NoContent();
+    if config.jwt.cookie {
+        let mut cookie = access_token_cookie(config, String::new());
+        cookie.make_removal();
+        response.cookie(cookie);
+    }
+    response.finish()
+}
+
+fn access_token_cookie(config: &Config, token: String) -> Cookie<'static> {
+    // HttpOnly：脚本读不到令牌；SameSite=Lax 挡住跨站请求携带它
+    Cookie::build(ACCESS_TOKEN_COOKIE, token)
+        .path("/api")
+        .http_only(true)
+        .secure(config.jwt.cookie_secure)
+        .same_site(SameSite::Lax)
+        .max_age(CookieDuration::seconds(
+            i64::try_from(config.jwt.expires_in_secs)
+                .expect("jwt.expires_in_secs is bounded by Config::validate"),
+        ))
+        .finish()
+}
+
+fn session_expires_at(config: &Config) -> chrono::NaiveDateTime {
+    let expires_in = i64::try_from(config.jwt.refresh_expires_in_secs)
+        .ok()
+        .and_then(TimeDelta::try_seconds)
+        .expect("jwt.refresh_expires_in_secs is bounded by Config::validate");
+    Utc::now().naive_utc() + expires_in
+}
+
+fn create_token(
+    config: &Config,
+    keys: &JwtKeys,
+    user_id: i64,
+    session_id: i64,
+) -> Result<String, AppError> {
+    // 创建 JWT 的 payload
+    let my_claims = Claims {
+        sub: user_id,
+        sid: session_id,
+        exp: SystemTime::now()
+            .add(Duration::from_secs(config.jwt.expires_in_secs))
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+    };
+
+    // 用当前签名密钥生成 JWT，header 带上 kid
+    keys.encode(&my_claims)
+}
+
 fn to_user_response(user: UserEntity, token: Option<String>) -> UserResponse {
     UserResponse {
         username: user.username,
         email: user.email,
-        token: token,
+        token,
+        refresh_token: None,
         bio: user.bio,
         image: user.image,
     }
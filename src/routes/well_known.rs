@@ -0,0 +1,18 @@
+use crate::jwt::JwtKeys;
+use actix_web::http::header::{CacheControl, CacheDirective};
+use actix_web::{get, web, HttpResponse, Responder};
+
+/// How long verifiers may cache the key set. A new key should be published
+/// at least this long before it starts signing.
+const JWKS_MAX_AGE_SECS: u32 = 300;
+
+/// The public keys that verify access tokens, for other services.
+#[get("/jwks.json")]
+pub async fn jwks(keys: web::Data<JwtKeys>) -> actix_web::Result<impl Responder> {
+    Ok(HttpResponse::Ok()
+        .insert_header(CacheControl(vec![
+            CacheDirective::Public,
+            CacheDirective::MaxAge(JWKS_MAX_AGE_SECS),
+        ]))
+        .json(keys.jwks()))
+}
@@ -0,0 +1,195 @@
+//! Full-text article search shared by the backends.
+//!
+//! A search string is reduced to lowercase word terms, each matching any word
+//! it is a prefix of (`rus` matches `Rust`). The database backends translate
+//! the terms to their own full-text syntax; the in-memory backend ranks with
+//! the inverted [`Index`] below. Snippets are highlighted here so every
+//! backend returns the same markup.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+
+/// Terms beyond this are ignored.
+const MAX_TERMS: usize = 8;
+/// Words of context kept around the first match in a body snippet.
+const SNIPPET_WORDS: usize = 30;
+/// Words shown before the first match in a body snippet.
+const SNIPPET_LEAD: usize = 8;
+const MARK_OPEN: &str = "<mark>";
+const MARK_CLOSE: &str = "</mark>";
+
+/// Relative weight of a match in the title, description and body.
+pub const FIELD_WEIGHTS: [f64; 3] = [10.0, 5.0, 1.0];
+
+/// The distinct lowercase words of `q`, in order, at most [`MAX_TERMS`].
+/// Only letters and digits survive, so terms are safe to embed in any
+/// backend's full-text query syntax.
+pub fn terms(q: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for range in words(q) {
+        let term = q[range].to_lowercase();
+        if !terms.contains(&term) {
+            terms.push(term);
+        }
+        if terms.len() == MAX_TERMS {
+            break;
+        }
+    }
+    terms
+}
+
+/// Byte ranges of the runs of letters and digits in `text`.
+fn words(text: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut chars = text.char_indices().peekable();
+    std::iter::from_fn(move || {
+        let (start, _) = chars.by_ref().find(|(_, c)| c.is_alphanumeric())?;
+        let mut end = text.len();
+        while let Some(&(i, c)) = chars.peek() {
+            if !c.is_alphanumeric() {
+                end = i;
+                break;
+            }
+            chars.next();
+        }
+        Some(start..end)
+    })
+}
+
+fn matches(word: &str, terms: &[String]) -> bool {
+    let word = word.to_lowercase();
+    terms.iter().any(|term| word.starts_with(term.as_str()))
+}
+
+/// Inverted index over article title, description and body.
+#[derive(Debug, Default)]
+pub struct Index {
+    /// Word → article id → field-weighted number of occurrences.
+    postings: BTreeMap<String, HashMap<i64, f64>>,
+    /// Article id → its indexed words, so an article can be dropped without
+    /// scanning every posting list.
+    documents: HashMap<i64, Vec<String>>,
+}
+
+impl Index {
+    /// Indexes the article, replacing what was indexed for it before.
+    pub fn insert(&mut self, id: i64, title: &str, description: &str, body: &str) {
+        self.remove(id);
+        let mut weights: HashMap<String, f64> = HashMap::new();
+        for (text, weight) in [title, description, body].into_iter().zip(FIELD_WEIGHTS) {
+            for range in words(text) {
+                *weights.entry(text[range].to_lowercase()).or_default() += weight;
+            }
+        }
+        let mut words = Vec::with_capacity(weights.len());
+        for (word, weight) in weights {
+            self.postings
+                .entry(word.clone())
+                .or_default()
+                .insert(id, weight);
+            words.push(word);
+        }
+        self.documents.insert(id, words);
+    }
+
+    pub fn remove(&mut self, id: i64) {
+        for word in self.documents.remove(&id).unwrap_or_default() {
+            if let Some(posting) = self.postings.get_mut(&word) {
+                posting.remove(&id);
+                if posting.is_empty() {
+                    self.postings.remove(&word);
+                }
+            }
+        }
+    }
+
+    /// Relevance of every article matching at least one term: per term, the
+    /// weighted occurrences of the words it prefixes times the term's inverse
+    /// document frequency.
+    pub fn search(&self, terms: &[String]) -> HashMap<i64, f64> {
+        let total = self.documents.len() as f64;
+        let mut scores: HashMap<i64, f64> = HashMap::new();
+        for term in terms {
+            let mut hits: HashMap<i64, f64> = HashMap::new();
+            let words = self
+                .postings
+                .range(term.clone()..)
+                .take_while(|(word, _)| word.starts_with(term.as_str()));
+            for (_, posting) in words {
+                for (id, weight) in posting {
+                    *hits.entry(*id).or_default() += weight;
+                }
+            }
+            let idf = (1.0 + total / hits.len().max(1) as f64).ln();
+            for (id, weight) in hits {
+                *scores.entry(id).or_default() += weight * idf;
+            }
+        }
+        scores
+    }
+}
+
+/// `text` HTML-escaped with every word matching a term wrapped in
+/// `<mark>..</mark>`, or `None` when nothing matches.
+pub fn highlight(text: &str, terms: &[String]) -> Option<String> {
+    excerpt(text, terms, usize::MAX)
+}
+
+/// Like [`highlight`], but cut to a window of words around the first match
+/// with `…` marking the omitted text.
+pub fn snippet(text: &str, terms: &[String]) -> Option<String> {
+    excerpt(text, terms, SNIPPET_WORDS)
+}
+
+fn excerpt(text: &str, terms: &[String], max_words: usize) -> Option<String> {
+    let words: Vec<Range<usize>> = words(text).collect();
+    let first = words
+        .iter()
+        .position(|range| matches(&text[range.clone()], terms))?;
+    let (start, end) = if max_words >= words.len() {
+        (0, words.len())
+    } else {
+        let start = first
+            .saturating_sub(SNIPPET_LEAD)
+            .min(words.len() - max_words);
+        (start, start + max_words)
+    };
+
+    let mut out = String::new();
+    let mut cursor = if start == 0 {
+        0
+    } else {
+        out.push('…');
+        words[start].start
+    };
+    for range in &words[start..end] {
+        escape_into(&mut out, &text[cursor..range.start]);
+        let word = &text[range.clone()];
+        if matches(word, terms) {
+            out.push_str(MARK_OPEN);
+            escape_into(&mut out, word);
+            out.push_str(MARK_CLOSE);
+        } else {
+            escape_into(&mut out, word);
+        }
+        cursor = range.end;
+    }
+    if end == words.len() {
+        escape_into(&mut out, &text[cursor..]);
+    } else {
+        out.push('…');
+    }
+    Some(out)
+}
+
+fn escape_into(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+}
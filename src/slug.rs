@@ -0,0 +1,68 @@
+//! Readable, unique article slugs.
+//!
+//! Titles are transliterated to lowercase ASCII words joined by `-`
+//! (`"Ünïcödé Tïtle"` → `unicode-title`); on collision a numeric suffix is
+//! appended (`unicode-title-2`, `unicode-title-3`, ..).
+
+use std::collections::HashSet;
+
+use slugify::slugify;
+
+/// Leaves room for the numeric suffix within the 255 character column.
+const MAX_BASE_LENGTH: usize = 200;
+/// Used when nothing of the title survives transliteration.
+const FALLBACK_SLUG: &str = "article";
+/// Fixed routes under `/api/articles/` that would shadow `{slug}`.
+const RESERVED_SLUGS: [&str; 2] = ["feed", "search"];
+
+/// The slug for `title` before collisions are taken into account.
+pub fn base_slug(title: &str) -> String {
+    let mut slug = slugify!(title);
+    if slug.len() > MAX_BASE_LENGTH {
+        slug.truncate(MAX_BASE_LENGTH);
+        slug = slug.trim_end_matches('-').to_string();
+    }
+    if slug.is_empty() {
+        FALLBACK_SLUG.to_string()
+    } else {
+        slug
+    }
+}
+
+/// `base` if it is neither `taken` nor reserved, otherwise `base-<n>` with the
+/// smallest free `n` starting from 2.
+pub fn unique_slug(base: &str, taken: &HashSet<String>) -> String {
+    if !taken.contains(base) && !RESERVED_SLUGS.contains(&base) {
+        return base.to_string();
+    }
+    (2..)
+        .map(|n| format!("{}-{}", base, n))
+        .find(|slug| !taken.contains(slug))
+        .expect("some suffix is free")
+}
+
+/// Whether an article at `slug` titled `old_title` keeps its slug when
+/// retitled `new_title`. A suffixed slug is only kept when the old title
+/// maps to the same base, so `hello-world-2024` from "Hello World 2024" is
+/// not mistaken for a collision suffix of "Hello World".
+pub fn keeps_slug(slug: &str, old_title: &str, new_title: &str) -> bool {
+    let base = base_slug(new_title);
+    slug == base || (base_slug(old_title) == base && is_derived_from(slug, &base))
+}
+
+/// Whether `slug` is `base` or `base-<n>`.
+pub fn is_derived_from(slug: &str, base: &str) -> bool {
+    match slug.strip_prefix(base) {
+        Some("") => true,
+        Some(suffix) => suffix
+            .strip_prefix('-')
+            .is_some_and(|n| !n.is_empty() && n.bytes().all(|c| c.is_ascii_digit())),
+        None => false,
+    }
+}
+
+/// `LIKE` pattern matching every suffixed variant of `base`. Slugs only
+/// contain `[a-z0-9-]`, so no escaping is needed.
+pub fn suffixed_pattern(base: &str) -> String {
+    format!("{}-%", base)
+}
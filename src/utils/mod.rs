@@ -1,4 +1,8 @@
 use bcrypt::{hash, verify};
+use data_encoding::{BASE64URL_NOPAD, HEXLOWER};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
 
 pub fn encrypt_password(password: String) -> String {
     // 生成密码的哈希值
@@ -10,6 +14,26 @@ pub fn encrypt_password(password: String) -> String {
 }
 
 pub fn verify_password(password: String, hash: &str) -> bool {
-    let is_verified = verify(password, hash).unwrap_or(false);
-    is_verified
-}
\ No newline at end of file
+    verify(password, hash).unwrap_or(false)
+}
+
+/// A bcrypt hash of a random password. Logins naming no user are checked
+/// against it so they take as long as a wrong password.
+pub fn dummy_password_hash() -> &'static str {
+    static HASH: OnceLock<String> = OnceLock::new();
+    HASH.get_or_init(|| encrypt_password(generate_token()))
+}
+
+/// A random opaque token (256 bits, base64url) for refresh tokens.
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    BASE64URL_NOPAD.encode(&bytes)
+}
+
+/// SHA-256 of `token` in hex. Only this digest is stored, so a leaked table
+/// does not hand out usable tokens; unlike passwords, the tokens are random
+/// enough that a fast hash is fine.
+pub fn hash_token(token: &str) -> String {
+    HEXLOWER.encode(&Sha256::digest(token.as_bytes()))
+}
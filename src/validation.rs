@@ -0,0 +1,176 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::ops::Deref;
+
+use actix_web::dev::Payload;
+use actix_web::error::JsonPayloadError;
+use actix_web::{web, FromRequest, HttpRequest};
+use futures::future::LocalBoxFuture;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer};
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use crate::error::AppError;
+
+pub const MAX_TAGS: usize = 10;
+pub const MAX_TAG_LENGTH: usize = 32;
+
+/// JSON body extractor that runs the payload's `Validate` rules and rejects
+/// the request with 422 and a per-field error map when they fail.
+#[derive(Debug)]
+pub struct ValidatedJson<T>(pub T);
+
+impl<T> ValidatedJson<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for ValidatedJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> FromRequest for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate + 'static,
+{
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let json = web::Json::<T>::from_request(req, payload);
+        Box::pin(async move {
+            let value = match json.await {
+                Ok(json) => json.into_inner(),
+                Err(e) => {
+                    // 反序列化失败（缺少字段、类型错误）同样按 422 返回
+                    return match e.as_error::<JsonPayloadError>() {
+                        Some(JsonPayloadError::Deserialize(de)) => {
+                            Err(AppError::validation("body", &de.to_string()).into())
+                        }
+                        _ => Err(e),
+                    };
+                }
+            };
+            value.validate().map_err(AppError::from)?;
+            Ok(ValidatedJson(value))
+        })
+    }
+}
+
+impl From<ValidationErrors> for AppError {
+    fn from(e: ValidationErrors) -> Self {
+        let errors: BTreeMap<String, Vec<String>> = e
+            .field_errors()
+            .into_iter()
+            .map(|(field, errors)| {
+                let messages = errors
+                    .iter()
+                    .map(|e| match &e.message {
+                        Some(message) => message.to_string(),
+                        None => e.code.to_string(),
+                    })
+                    .collect();
+                (field.to_string(), messages)
+            })
+            .collect();
+        AppError::Validation(errors)
+    }
+}
+
+fn error(message: &'static str) -> ValidationError {
+    let mut e = ValidationError::new("invalid");
+    e.message = Some(Cow::Borrowed(message));
+    e
+}
+
+pub fn not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        return Err(error("can't be blank"));
+    }
+    Ok(())
+}
+
+pub fn username(value: &str) -> Result<(), ValidationError> {
+    not_blank(value)?;
+    if !value
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+    {
+        return Err(error("may only contain letters, digits, '.', '_' and '-'"));
+    }
+    Ok(())
+}
+
+/// Empty string clears the image, anything else must be an http(s) URL.
+pub fn image_url(value: &str) -> Result<(), ValidationError> {
+    if value.is_empty() {
+        return Ok(());
+    }
+    if !(value.starts_with("http://") || value.starts_with("https://"))
+        || !validator::validate_url(value)
+    {
+        return Err(error("is not a valid http(s) URL"));
+    }
+    Ok(())
+}
+
+/// Checks a `tagList` as deserialized by [`normalized_tags`], so the rules
+/// apply to the very names that get stored.
+pub fn tag_list(tags: &[String]) -> Result<(), ValidationError> {
+    if tags.len() > MAX_TAGS {
+        return Err(error("has too many tags (maximum is 10)"));
+    }
+    for tag in tags {
+        if tag.is_empty() {
+            return Err(error("can't contain blank tags"));
+        }
+        if tag.chars().count() > MAX_TAG_LENGTH {
+            return Err(error("can't contain tags longer than 32 characters"));
+        }
+        // 只收 ASCII，小写转换在 Rust 和各数据库里结果一致
+        if !tag
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.+#".contains(c))
+        {
+            return Err(error(
+                "tags may only contain ASCII letters, digits and '-', '_', '.', '+', '#'",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The stored form of a tag: trimmed and lowercased, so `Rust` and ` rust`
+/// are the same tag on every backend whatever its collation. Only ASCII
+/// letters are lowercased, as SQLite's `LOWER` and the tag migrations do.
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim().to_ascii_lowercase()
+}
+
+/// Deserializes a `tagList`, normalizing each tag and dropping repeats.
+pub fn normalized_tags<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mut tags: Vec<String> = Vec::new();
+    for tag in Vec::<String>::deserialize(deserializer)? {
+        let tag = normalize_tag(&tag);
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    Ok(tags)
+}
+
+/// [`normalized_tags`] for a `tagList` that may be left out.
+pub fn normalized_optional_tags<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    normalized_tags(deserializer).map(Some)
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use serde_json::{json, Value};
+
+#[actix_web::test]
+async fn register_login_create_and_get_article() {
+    let app = test::init_service(common::app()).await;
+
+    let res = test::call_service(&app, common::register("alice").to_request()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = test::call_service(&app, common::login("alice").to_request()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: Value = test::read_body_json(res).await;
+    assert_eq!(body["user"]["username"], "alice");
+    let token = body["user"]["token"]
+        .as_str()
+        .expect("login returns a token")
+        .to_string();
+
+    let req = common::create_article(&token, "How to train your dragon").to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: Value = test::read_body_json(res).await;
+    let slug = body["article"]["slug"].as_str().unwrap().to_string();
+    assert_eq!(slug, "how-to-train-your-dragon");
+    assert_eq!(body["article"]["favoritesCount"], 0);
+
+    let req = TestRequest::get()
+        .uri(&format!("/api/articles/{}", slug))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: Value = test::read_body_json(res).await;
+    assert_eq!(body["article"]["title"], "How to train your dragon");
+    assert_eq!(body["article"]["author"]["username"], "alice");
+    assert_eq!(body["article"]["tagList"], json!(["dragons", "training"]));
+    assert_eq!(body["article"]["favorited"], false);
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use std::time::Duration;
+
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use serde_json::{json, Value};
+
+#[actix_web::test]
+async fn deleting_an_article_removes_its_comments_favorites_and_tags() {
+    let app = test::init_service(common::app()).await;
+    let body: Value =
+        test::call_and_read_body_json(&app, common::register("alice").to_request()).await;
+    let alice = body["user"]["token"].as_str().unwrap().to_string();
+    let body: Value =
+        test::call_and_read_body_json(&app, common::register("bob").to_request()).await;
+    let bob = body["user"]["token"].as_str().unwrap().to_string();
+    let req = common::create_article_with_tags(&alice, "Dragons", &["dragons"]).to_request();
+    test::call_service(&app, req).await;
+    let req = common::create_article_with_tags(&alice, "Knights", &["knights"]).to_request();
+    test::call_service(&app, req).await;
+    test::call_service(&app, common::favorite(&bob, "dragons").to_request()).await;
+    let req = common::comment(&bob, "dragons", "Great read").to_request();
+    test::call_service(&app, req).await;
+
+    let req = common::authorized(TestRequest::delete(), &alice).uri("/api/articles/dragons");
+    let res = test::call_service(&app, req.to_request()).await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+    let req = TestRequest::get().uri("/api/tags").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["tags"], json!(["knights"]));
+    let req = TestRequest::get()
+        .uri("/api/articles?favorited=bob")
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["articlesCount"], 0);
+    let req = TestRequest::get()
+        .uri("/api/articles/dragons/comments")
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    // 同名文章重新发布时不继承旧文章的任何数据
+    let req = common::create_article_with_tags(&alice, "Dragons", &[]).to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["article"]["slug"], "dragons");
+    assert_eq!(body["article"]["favoritesCount"], 0);
+    assert_eq!(body["article"]["tagList"], json!([]));
+    let req = TestRequest::get()
+        .uri("/api/articles/dragons/comments")
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["comments"], json!([]));
+}
+
+fn update(token: &str, slug: &str, article: Value) -> TestRequest {
+    common::authorized(TestRequest::put(), token)
+        .uri(&format!("/api/articles/{}", slug))
+        .set_json(json!({ "article": article }))
+}
+
+#[actix_web::test]
+async fn updating_the_tag_list_replaces_every_tag() {
+    let app = test::init_service(common::app()).await;
+    let body: Value =
+        test::call_and_read_body_json(&app, common::register("alice").to_request()).await;
+    let token = body["user"]["token"].as_str().unwrap();
+    let req = common::create_article(token, "Dragons").to_request();
+    let created: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(
+        created["article"]["tagList"],
+        json!(["dragons", "training"])
+    );
+
+    // 保证 updatedAt 的毫秒数变化
+    std::thread::sleep(Duration::from_millis(5));
+    let req = update(
+        token,
+        "dragons",
+        json!({ "tagList": ["training", "Fantasy"] }),
+    );
+    let body: Value = test::call_and_read_body_json(&app, req.to_request()).await;
+    assert_eq!(body["article"]["tagList"], json!(["fantasy", "training"]));
+    assert!(body["article"]["updatedAt"].as_str() > created["article"]["updatedAt"].as_str());
+
+    let req = TestRequest::get().uri("/api/tags").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let mut tags: Vec<&str> = body["tags"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tag| tag.as_str().unwrap())
+        .collect();
+    tags.sort_unstable();
+    assert_eq!(tags, ["fantasy", "training"]);
+    for (tag, count) in [("dragons", 0), ("fantasy", 1)] {
+        let req = TestRequest::get()
+            .uri(&format!("/api/articles?tag={}", tag))
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["articlesCount"], count, "{}", tag);
+    }
+
+    // 不带 tagList 时保留原有标签，空列表则全部清掉
+    let req = update(token, "dragons", json!({ "body": "A new body" }));
+    let body: Value = test::call_and_read_body_json(&app, req.to_request()).await;
+    assert_eq!(body["article"]["tagList"], json!(["fantasy", "training"]));
+    let req = update(token, "dragons", json!({ "tagList": [] }));
+    let body: Value = test::call_and_read_body_json(&app, req.to_request()).await;
+    assert_eq!(body["article"]["tagList"], json!([]));
+    let req = TestRequest::get().uri("/api/tags").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["tags"], json!([]));
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use actix_web::cookie::Cookie;
+use actix_web::http::{header, StatusCode};
+use actix_web::test::{self, TestRequest};
+use serde_json::Value;
+
+use realworld_rust_actix_web::ACCESS_TOKEN_COOKIE;
+
+fn current_user() -> TestRequest {
+    TestRequest::get().uri("/api/user")
+}
+
+#[actix_web::test]
+async fn unauthenticated_requests_get_a_bearer_challenge() {
+    let app = test::init_service(common::app()).await;
+
+    for (req, challenge) in [
+        (current_user(), "Bearer realm=\"realworld\""),
+        (
+            current_user().insert_header((header::AUTHORIZATION, "Basic dXNlcjpwYXNz")),
+            "Bearer realm=\"realworld\", error=\"invalid_request\", \
+             error_description=\"unsupported authorization scheme\"",
+        ),
+        (
+            common::authorized(current_user(), "not.a.jwt"),
+            "Bearer realm=\"realworld\", error=\"invalid_token\"",
+        ),
+    ] {
+        let res = test::call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        let www_authenticate = res
+            .headers()
+            .get(header::WWW_AUTHENTICATE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        assert!(
+            www_authenticate.starts_with(challenge),
+            "{:?} does not start with {:?}",
+            www_authenticate,
+            challenge
+        );
+        let body: Value = test::read_body_json(res).await;
+        assert!(body["errors"]["token"].is_array());
+    }
+}
+
+#[actix_web::test]
+async fn both_schemes_authenticate() {
+    let app = test::init_service(common::app()).await;
+    let body: Value =
+        test::call_and_read_body_json(&app, common::register("alice").to_request()).await;
+    let token = body["user"]["token"].as_str().unwrap();
+
+    for scheme in ["Token", "Bearer", "bearer"] {
+        let req = current_user()
+            .insert_header((header::AUTHORIZATION, format!("{} {}", scheme, token)))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK, "{}", scheme);
+    }
+}
+
+#[actix_web::test]
+async fn the_cookie_authenticates_only_when_enabled() {
+    let mut config = common::config();
+    config.jwt.cookie = true;
+    let app = test::init_service(common::app_with(config)).await;
+    let res = test::call_service(&app, common::register("alice").to_request()).await;
+    let token = res
+        .response()
+        .cookies()
+        .find(|cookie| cookie.name() == ACCESS_TOKEN_COOKIE)
+        .map(|cookie| cookie.value().to_string())
+        .expect("registration sets the token cookie");
+
+    let req = current_user()
+        .cookie(Cookie::new(ACCESS_TOKEN_COOKIE, token.clone()))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    // 未开启 jwt.cookie 时不从 Cookie 取令牌
+    let app = test::init_service(common::app()).await;
+    let req = current_user()
+        .cookie(Cookie::new(ACCESS_TOKEN_COOKIE, token))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        res.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+        "Bearer realm=\"realworld\""
+    );
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use serde_json::{json, Value};
+
+fn delete_comment(token: &str, slug: &str, id: &Value) -> TestRequest {
+    common::authorized(TestRequest::delete(), token)
+        .uri(&format!("/api/articles/{}/comments/{}", slug, id))
+}
+
+#[actix_web::test]
+async fn comments_are_deleted_by_their_author_or_the_article_author_only() {
+    let app = test::init_service(common::app()).await;
+    let mut tokens = Vec::new();
+    for username in ["alice", "bob", "carol"] {
+        let body: Value =
+            test::call_and_read_body_json(&app, common::register(username).to_request()).await;
+        tokens.push(body["user"]["token"].as_str().unwrap().to_string());
+    }
+    let (alice, bob, carol) = (&tokens[0], &tokens[1], &tokens[2]);
+    test::call_service(&app, common::create_article(alice, "Dragons").to_request()).await;
+    let mut ids = Vec::new();
+    for body in ["First", "Second"] {
+        let req = common::comment(bob, "dragons", body).to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        ids.push(body["comment"]["id"].clone());
+    }
+
+    let res =
+        test::call_service(&app, delete_comment(carol, "dragons", &ids[0]).to_request()).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    let res = test::call_service(&app, delete_comment(bob, "dragons", &ids[0]).to_request()).await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    let res =
+        test::call_service(&app, delete_comment(alice, "dragons", &ids[1]).to_request()).await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+    let req = TestRequest::get()
+        .uri("/api/articles/dragons/comments")
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["comments"], json!([]));
+}
+
+#[actix_web::test]
+async fn the_policy_can_keep_article_authors_from_deleting_comments() {
+    let mut config = common::config();
+    config.policy.article_author_can_delete_comments = false;
+    let app = test::init_service(common::app_with(config)).await;
+    let body: Value =
+        test::call_and_read_body_json(&app, common::register("alice").to_request()).await;
+    let alice = body["user"]["token"].as_str().unwrap().to_string();
+    let body: Value =
+        test::call_and_read_body_json(&app, common::register("bob").to_request()).await;
+    let bob = body["user"]["token"].as_str().unwrap().to_string();
+    test::call_service(&app, common::create_article(&alice, "Dragons").to_request()).await;
+    let req = common::comment(&bob, "dragons", "First").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+
+    let req = delete_comment(&alice, "dragons", &body["comment"]["id"]).to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+async fn comments_are_only_found_under_their_own_article() {
+    let app = test::init_service(common::app()).await;
+    let body: Value =
+        test::call_and_read_body_json(&app, common::register("alice").to_request()).await;
+    let token = body["user"]["token"].as_str().unwrap();
+    for title in ["Dragons", "Knights"] {
+        test::call_service(&app, common::create_article(token, title).to_request()).await;
+    }
+    let req = common::comment(token, "dragons", "First").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let id = &body["comment"]["id"];
+
+    let res = test::call_service(&app, delete_comment(token, "knights", id).to_request()).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    let res = test::call_service(
+        &app,
+        delete_comment(token, "dragons", &json!(999)).to_request(),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    let res = test::call_service(&app, delete_comment(token, "dragons", id).to_request()).await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+}
+
+#[actix_web::test]
+async fn only_the_author_updates_or_deletes_an_article() {
+    let app = test::init_service(common::app()).await;
+    let body: Value =
+        test::call_and_read_body_json(&app, common::register("alice").to_request()).await;
+    let alice = body["user"]["token"].as_str().unwrap().to_string();
+    let body: Value =
+        test::call_and_read_body_json(&app, common::register("bob").to_request()).await;
+    let bob = body["user"]["token"].as_str().unwrap().to_string();
+    test::call_service(&app, common::create_article(&alice, "Dragons").to_request()).await;
+
+    let req = common::authorized(TestRequest::put(), &bob)
+        .uri("/api/articles/dragons")
+        .set_json(json!({ "article": { "title": "Mine now" } }));
+    let res = test::call_service(&app, req.to_request()).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    let req = common::authorized(TestRequest::delete(), &bob).uri("/api/articles/dragons");
+    let res = test::call_service(&app, req.to_request()).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let req = TestRequest::get().uri("/api/articles/dragons").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["article"]["title"], "Dragons");
+}
@@ -0,0 +1,133 @@
+//! Helpers shared by the API tests. The app runs around an
+//! `InMemoryRepository`, so no database is needed.
+
+// 每个测试文件只用到其中一部分
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
+use actix_web::http::header;
+use actix_web::test::TestRequest;
+use actix_web::{web, App};
+use serde_json::json;
+
+use realworld_rust_actix_web::config::Config;
+use realworld_rust_actix_web::jwt::JwtKeys;
+use realworld_rust_actix_web::persistence::memory::InMemoryRepository;
+use realworld_rust_actix_web::persistence::Repository;
+use realworld_rust_actix_web::rate_limit::{LoginLimiter, MemoryStore, RateLimits};
+use realworld_rust_actix_web::routes;
+
+pub const PASSWORD: &str = "correct horse battery staple";
+
+/// The defaults with an HS256 secret set.
+pub fn config() -> Config {
+    let mut config = Config::default();
+    config.jwt.secret = "a-test-secret-of-at-least-32-bytes".to_string();
+    config
+}
+
+/// The API with the app data `main` registers, around an empty repository.
+pub fn app() -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    app_with(config())
+}
+
+/// Like [`app`], with `config` in place of the test defaults.
+pub fn app_with(
+    config: Config,
+) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    app_with_repository(config, Arc::new(InMemoryRepository::new()))
+}
+
+/// Like [`app_with`], around `repository`, so that several apps, e.g. before
+/// and after a key rotation, share their users and sessions.
+pub fn app_with_repository(
+    config: Config,
+    repository: Arc<dyn Repository>,
+) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    let keys = JwtKeys::load(&config.jwt).expect("the test keys load");
+    let rate_limits = RateLimits::new(&config, Arc::new(MemoryStore::default()));
+
+    App::new()
+        .app_data(web::Data::from(repository))
+        .app_data(web::Data::new(LoginLimiter::new(&config.login)))
+        .app_data(web::Data::new(rate_limits))
+        .app_data(web::Data::new(keys))
+        .app_data(web::Data::new(config))
+        .configure(routes::configure)
+}
+
+/// Registers `username` with the email `<username>@example.com`.
+pub fn register(username: &str) -> TestRequest {
+    TestRequest::post().uri("/api/users").set_json(json!({
+        "user": {
+            "username": username,
+            "email": format!("{}@example.com", username),
+            "password": PASSWORD,
+        }
+    }))
+}
+
+pub fn login(username: &str) -> TestRequest {
+    login_with(&format!("{}@example.com", username), PASSWORD)
+}
+
+pub fn login_with(email: &str, password: &str) -> TestRequest {
+    TestRequest::post().uri("/api/users/login").set_json(json!({
+        "user": { "email": email, "password": password }
+    }))
+}
+
+pub fn create_article(token: &str, title: &str) -> TestRequest {
+    create_article_with_tags(token, title, &["dragons", "training"])
+}
+
+pub fn create_article_with_tags(token: &str, title: &str, tags: &[&str]) -> TestRequest {
+    authorized(TestRequest::post().uri("/api/articles"), token).set_json(json!({
+        "article": {
+            "title": title,
+            "description": "A description",
+            "body": "A body",
+            "tagList": tags,
+        }
+    }))
+}
+
+pub fn authorized(req: TestRequest, token: &str) -> TestRequest {
+    req.insert_header((header::AUTHORIZATION, format!("Token {}", token)))
+}
+
+pub fn favorite(token: &str, slug: &str) -> TestRequest {
+    authorized(TestRequest::post(), token).uri(&format!("/api/articles/{}/favorite", slug))
+}
+
+pub fn comment(token: &str, slug: &str, body: &str) -> TestRequest {
+    authorized(TestRequest::post(), token)
+        .uri(&format!("/api/articles/{}/comments", slug))
+        .set_json(json!({ "comment": { "body": body } }))
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use std::sync::Arc;
+
+use actix_web::http::{header, StatusCode};
+use actix_web::test::{self, TestRequest};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde_json::{json, Value};
+
+use realworld_rust_actix_web::config::{Config, JwtKeyConfig};
+use realworld_rust_actix_web::persistence::memory::InMemoryRepository;
+use realworld_rust_actix_web::persistence::Repository;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+fn hs256(id: &str) -> JwtKeyConfig {
+    JwtKeyConfig {
+        id: id.to_string(),
+        algorithm: Algorithm::HS256,
+        secret: Some(format!("secret-of-{}-at-least-32-bytes-long", id)),
+        private_key_file: None,
+        public_key_file: None,
+    }
+}
+
+fn es256(id: &str) -> JwtKeyConfig {
+    JwtKeyConfig {
+        id: id.to_string(),
+        algorithm: Algorithm::ES256,
+        secret: None,
+        private_key_file: Some(format!("{}/es256-private.pem", FIXTURES)),
+        public_key_file: Some(format!("{}/es256-public.pem", FIXTURES)),
+    }
+}
+
+fn config_with(keys: Vec<JwtKeyConfig>, signing_key: &str) -> Config {
+    let mut config = common::config();
+    config.jwt.keys = keys;
+    config.jwt.signing_key = signing_key.to_string();
+    config
+}
+
+fn current_user(token: &str) -> TestRequest {
+    common::authorized(TestRequest::get().uri("/api/user"), token)
+}
+
+fn token(body: &Value) -> String {
+    body["user"]["token"].as_str().unwrap().to_string()
+}
+
+#[actix_web::test]
+async fn tokens_of_a_rotated_out_key_verify_until_it_is_dropped() {
+    let repository: Arc<dyn Repository> = Arc::new(InMemoryRepository::new());
+    let before = config_with(vec![hs256("2024")], "2024");
+    let app = test::init_service(common::app_with_repository(before, repository.clone())).await;
+    let body: Value =
+        test::call_and_read_body_json(&app, common::register("alice").to_request()).await;
+    let old_token = token(&body);
+    let header = jsonwebtoken::decode_header(&old_token).unwrap();
+    assert_eq!(header.kid.as_deref(), Some("2024"));
+
+    // 新密钥签发，旧密钥仍在 keys 里用来验证
+    let rotated = config_with(vec![hs256("2024"), es256("2025")], "2025");
+    let app = test::init_service(common::app_with_repository(rotated, repository.clone())).await;
+    let body: Value =
+        test::call_and_read_body_json(&app, common::login("alice").to_request()).await;
+    let new_token = token(&body);
+    let header = jsonwebtoken::decode_header(&new_token).unwrap();
+    assert_eq!(header.kid.as_deref(), Some("2025"));
+    assert_eq!(header.alg, Algorithm::ES256);
+    for token in [&old_token, &new_token] {
+        let res = test::call_service(&app, current_user(token).to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    let dropped = config_with(vec![es256("2025")], "2025");
+    let app = test::init_service(common::app_with_repository(dropped, repository)).await;
+    let res = test::call_service(&app, current_user(&old_token).to_request()).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    let res = test::call_service(&app, current_user(&new_token).to_request()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn the_jwks_lists_public_keys_that_verify_issued_tokens() {
+    let config = config_with(vec![hs256("2024"), es256("2025")], "2025");
+    let app = test::init_service(common::app_with(config)).await;
+    let body: Value =
+        test::call_and_read_body_json(&app, common::register("alice").to_request()).await;
+    let token = token(&body);
+
+    let req = TestRequest::get()
+        .uri("/.well-known/jwks.json")
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let cache_control = res.headers().get(header::CACHE_CONTROL).unwrap();
+    assert_eq!(cache_control, "public, max-age=300");
+    let body: Value = test::read_body_json(res).await;
+
+    // HS256 的密钥是共享秘密，不能公开
+    let keys = body["keys"].as_array().unwrap();
+    assert_eq!(keys.len(), 1);
+    let key = &keys[0];
+    assert_eq!(key["kid"], "2025");
+    assert_eq!(key["kty"], "EC");
+    assert_eq!(key["crv"], "P-256");
+    assert_eq!(key["alg"], "ES256");
+    assert_eq!(key["use"], "sig");
+    assert!(key.get("d").is_none() && key.get("k").is_none());
+    assert!(!body.to_string().contains("secret-of-2024"));
+
+    // 别的服务只凭 JWKS 就能验证令牌
+    let jwks: JwkSet = serde_json::from_value(body).unwrap();
+    let jwk = jwks.find("2025").unwrap();
+    let key = DecodingKey::from_jwk(jwk).unwrap();
+    jsonwebtoken::decode::<Value>(&token, &key, &Validation::new(Algorithm::ES256)).unwrap();
+}
+
+#[actix_web::test]
+async fn the_jwks_is_empty_with_only_hs256_keys() {
+    let app = test::init_service(common::app()).await;
+    let req = TestRequest::get()
+        .uri("/.well-known/jwks.json")
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body, json!({ "keys": [] }));
+}
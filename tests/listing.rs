@@ -0,0 +1,231 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use serde_json::Value;
+
+fn list(query: &str) -> TestRequest {
+    TestRequest::get().uri(&format!("/api/articles?{}", query))
+}
+
+fn titles(body: &Value) -> Vec<&str> {
+    body["articles"]
+        .as_array()
+        .expect("articles is an array")
+        .iter()
+        .map(|article| article["title"].as_str().unwrap())
+        .collect()
+}
+
+#[actix_web::test]
+async fn cursors_page_forward_and_back() {
+    let app = test::init_service(common::app()).await;
+    let body: Value =
+        test::call_and_read_body_json(&app, common::register("alice").to_request()).await;
+    let token = body["user"]["token"].as_str().unwrap();
+    for title in ["One", "Two", "Three", "Four", "Five"] {
+        test::call_service(&app, common::create_article(token, title).to_request()).await;
+    }
+
+    let first: Value = test::call_and_read_body_json(&app, list("limit=2").to_request()).await;
+    assert_eq!(titles(&first), ["Five", "Four"]);
+    assert_eq!(first["articlesCount"], 5);
+    assert!(first.get("prevCursor").is_none());
+
+    let next = first["nextCursor"].as_str().unwrap();
+    let req = list(&format!("limit=2&after={}", next)).to_request();
+    let second: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(titles(&second), ["Three", "Two"]);
+
+    let next = second["nextCursor"].as_str().unwrap();
+    let req = list(&format!("limit=2&after={}", next)).to_request();
+    let last: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(titles(&last), ["One"]);
+    assert!(last.get("nextCursor").is_none());
+
+    // 从最后一页往回翻，顺序不变
+    let prev = last["prevCursor"].as_str().unwrap();
+    let req = list(&format!("limit=2&before={}", prev)).to_request();
+    let back: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(titles(&back), ["Three", "Two"]);
+
+    let prev = back["prevCursor"].as_str().unwrap();
+    let req = list(&format!("limit=2&before={}", prev)).to_request();
+    let back: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(titles(&back), ["Five", "Four"]);
+    assert!(back.get("prevCursor").is_none());
+
+    let res = test::call_service(&app, list("after=garbage").to_request()).await;
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[actix_web::test]
+async fn popularity_sorts_rank_articles() {
+    let app = test::init_service(common::app()).await;
+    let mut tokens = Vec::new();
+    for name in ["alice", "bob", "carol"] {
+        let body: Value =
+            test::call_and_read_body_json(&app, common::register(name).to_request()).await;
+        tokens.push(body["user"]["token"].as_str().unwrap().to_string());
+    }
+    for title in ["Loved", "Discussed", "Ignored"] {
+        test::call_service(&app, common::create_article(&tokens[0], title).to_request()).await;
+    }
+    for token in &tokens[1..] {
+        test::call_service(&app, common::favorite(token, "loved").to_request()).await;
+    }
+    test::call_service(&app, common::favorite(&tokens[0], "discussed").to_request()).await;
+    for body in ["First", "Second", "Third"] {
+        let req = common::comment(&tokens[1], "discussed", body).to_request();
+        test::call_service(&app, req).await;
+    }
+
+    for (sort, expected) in [
+        ("latest", ["Ignored", "Discussed", "Loved"]),
+        ("oldest", ["Loved", "Discussed", "Ignored"]),
+        ("most_favorited", ["Loved", "Discussed", "Ignored"]),
+        ("most_commented", ["Discussed", "Ignored", "Loved"]),
+        ("trending", ["Loved", "Discussed", "Ignored"]),
+    ] {
+        let req = list(&format!("sort={}", sort)).to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(titles(&body), expected, "sort={}", sort);
+        if sort != "latest" && sort != "oldest" {
+            assert!(body.get("nextCursor").is_none(), "sort={}", sort);
+        }
+    }
+}
+
+#[actix_web::test]
+async fn unknown_sorts_and_cursors_with_popularity_sorts_are_rejected() {
+    let app = test::init_service(common::app()).await;
+    let body: Value =
+        test::call_and_read_body_json(&app, common::register("alice").to_request()).await;
+    let token = body["user"]["token"].as_str().unwrap();
+    test::call_service(&app, common::create_article(token, "One").to_request()).await;
+    test::call_service(&app, common::create_article(token, "Two").to_request()).await;
+    let body: Value = test::call_and_read_body_json(&app, list("limit=1").to_request()).await;
+    let cursor = body["nextCursor"].as_str().unwrap();
+
+    for (query, field) in [
+        ("sort=id%20desc".to_string(), "sort"),
+        ("sort=LATEST".to_string(), "sort"),
+        (format!("sort=trending&after={}", cursor), "cursor"),
+        (format!("sort=most_favorited&before={}", cursor), "cursor"),
+    ] {
+        let res = test::call_service(&app, list(&query).to_request()).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY, "{}", query);
+        let body: Value = test::read_body_json(res).await;
+        assert!(body["errors"][field].is_array(), "{}", query);
+    }
+}
+
+#[actix_web::test]
+async fn filters_combine_and_repeat() {
+    let app = test::init_service(common::app()).await;
+    let mut tokens = Vec::new();
+    for name in ["alice", "bob", "carol"] {
+        let body: Value =
+            test::call_and_read_body_json(&app, common::register(name).to_request()).await;
+        tokens.push(body["user"]["token"].as_str().unwrap().to_string());
+    }
+    let (alice, bob, carol) = (&tokens[0], &tokens[1], &tokens[2]);
+    for (token, title, tags) in [
+        (alice, "Alice on Rust", &["rust"][..]),
+        (alice, "Alice on the web", &["web"][..]),
+        (alice, "Alice on Rust and the web", &["rust", "web"][..]),
+        (bob, "Bob on Rust", &["rust"][..]),
+    ] {
+        let req = common::create_article_with_tags(token, title, tags).to_request();
+        test::call_service(&app, req).await;
+    }
+    for slug in ["alice-on-rust", "bob-on-rust", "alice-on-the-web"] {
+        test::call_service(&app, common::favorite(carol, slug).to_request()).await;
+    }
+
+    for (query, expected) in [
+        (
+            "author=alice&author=bob&tag=rust&favorited=carol",
+            &["Bob on Rust", "Alice on Rust"][..],
+        ),
+        (
+            "tag=rust&tag=web&tag_match=all",
+            &["Alice on Rust and the web"][..],
+        ),
+        (
+            "author=alice&tag=rust&tag=web",
+            &[
+                "Alice on Rust and the web",
+                "Alice on the web",
+                "Alice on Rust",
+            ][..],
+        ),
+        ("author=bob&tag=web", &[][..]),
+    ] {
+        let body: Value = test::call_and_read_body_json(&app, list(query).to_request()).await;
+        assert_eq!(titles(&body), expected, "{}", query);
+        assert_eq!(body["articlesCount"], expected.len(), "{}", query);
+    }
+
+    // exclude_own 需要登录，去掉自己的文章
+    let req = common::authorized(list("tag=rust&exclude_own=true"), alice).to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(titles(&body), ["Bob on Rust"]);
+
+    for (query, field) in [
+        ("tags=rust", "query"),
+        ("favorited=carol&favorited=bob", "favorited"),
+        ("limit=1&limit=2", "limit"),
+    ] {
+        let res = test::call_service(&app, list(query).to_request()).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY, "{}", query);
+        let body: Value = test::read_body_json(res).await;
+        assert!(body["errors"][field].is_array(), "{}", query);
+    }
+}
+
+#[actix_web::test]
+async fn articles_count_covers_every_page_of_the_filtered_list() {
+    let app = test::init_service(common::app()).await;
+    let mut tokens = Vec::new();
+    for name in ["alice", "bob"] {
+        let body: Value =
+            test::call_and_read_body_json(&app, common::register(name).to_request()).await;
+        tokens.push(body["user"]["token"].as_str().unwrap().to_string());
+    }
+    let (alice, bob) = (&tokens[0], &tokens[1]);
+    for (token, title, tag) in [
+        (alice, "One", "rust"),
+        (alice, "Two", "rust"),
+        (alice, "Three", "rust"),
+        (alice, "Four", "web"),
+        (bob, "Five", "rust"),
+    ] {
+        let req = common::create_article_with_tags(token, title, &[tag]).to_request();
+        test::call_service(&app, req).await;
+    }
+    for slug in ["one", "four"] {
+        test::call_service(&app, common::favorite(bob, slug).to_request()).await;
+    }
+
+    for (query, page, count) in [
+        ("tag=rust&limit=1", 1, 4),
+        ("tag=rust&limit=2&offset=3", 1, 4),
+        ("author=alice&offset=10", 0, 4),
+        ("favorited=bob&limit=1", 1, 2),
+        ("tag=rust&author=bob", 1, 1),
+        ("tag=go", 0, 0),
+    ] {
+        let body: Value = test::call_and_read_body_json(&app, list(query).to_request()).await;
+        assert_eq!(titles(&body).len(), page, "{}", query);
+        assert_eq!(body["articlesCount"], count, "{}", query);
+    }
+
+    // 关注流同样按全部结果计数
+    let req = common::authorized(TestRequest::post(), bob).uri("/api/profiles/alice/follow");
+    test::call_service(&app, req.to_request()).await;
+    let req = common::authorized(TestRequest::get(), bob).uri("/api/articles/feed?limit=1");
+    let body: Value = test::call_and_read_body_json(&app, req.to_request()).await;
+    assert_eq!(titles(&body), ["Four"]);
+    assert_eq!(body["articlesCount"], 4);
+}
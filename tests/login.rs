@@ -0,0 +1,83 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test;
+use serde_json::Value;
+
+#[actix_web::test]
+async fn repeated_failures_lock_the_account_without_revealing_it() {
+    let mut config = common::config();
+    config.login.lockout_threshold = 3;
+    config.login.ip_attempts_per_minute = 100;
+    config.login.account_attempts_per_minute = 100;
+    let app = test::init_service(common::app_with(config)).await;
+    test::call_service(&app, common::register("alice").to_request()).await;
+
+    let unknown = || common::login_with("nobody@example.com", common::PASSWORD);
+    let wrong_password = || common::login_with("alice@example.com", "wrong password");
+
+    let res = test::call_service(&app, unknown().to_request()).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    let refused: Value = test::read_body_json(res).await;
+
+    for _ in 0..3 {
+        let res = test::call_service(&app, wrong_password().to_request()).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        let body: Value = test::read_body_json(res).await;
+        assert_eq!(body, refused);
+    }
+
+    // 第 3 次失败后锁定，正确的密码也被拒绝，响应和未知邮箱一样
+    let res = test::call_service(&app, common::login("alice").to_request()).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    let body: Value = test::read_body_json(res).await;
+    assert_eq!(body, refused);
+
+    for _ in 0..3 {
+        let res = test::call_service(&app, unknown().to_request()).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        let body: Value = test::read_body_json(res).await;
+        assert_eq!(body, refused);
+    }
+}
+
+#[actix_web::test]
+async fn a_successful_login_resets_the_failure_count() {
+    let mut config = common::config();
+    config.login.lockout_threshold = 3;
+    config.login.account_attempts_per_minute = 100;
+    let app = test::init_service(common::app_with(config)).await;
+    test::call_service(&app, common::register("alice").to_request()).await;
+
+    for _ in 0..2 {
+        let req = common::login_with("alice@example.com", "wrong password").to_request();
+        test::call_service(&app, req).await;
+    }
+    let res = test::call_service(&app, common::login("alice").to_request()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let req = common::login_with("alice@example.com", "wrong password").to_request();
+    test::call_service(&app, req).await;
+    let res = test::call_service(&app, common::login("alice").to_request()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn attempts_per_account_are_limited() {
+    let mut config = common::config();
+    config.login.account_attempts_per_minute = 2;
+    config.login.ip_attempts_per_minute = 100;
+    let app = test::init_service(common::app_with(config)).await;
+    test::call_service(&app, common::register("alice").to_request()).await;
+
+    for _ in 0..2 {
+        let res = test::call_service(&app, common::login("alice").to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+    let res = test::call_service(&app, common::login("alice").to_request()).await;
+    assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // 其他账号不受影响
+    let res = test::call_service(&app, common::login("bob").to_request()).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+}
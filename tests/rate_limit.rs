@@ -0,0 +1,34 @@
+mod common;
+
+use actix_web::dev::ServiceResponse;
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+
+fn header<'a>(res: &'a ServiceResponse, name: &str) -> &'a str {
+    res.headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+}
+
+#[actix_web::test]
+async fn clients_are_told_their_quota_and_refused_once_it_runs_out() {
+    let mut config = common::config();
+    config.rate_limit.reads_per_minute = 2;
+    let app = test::init_service(common::app_with(config)).await;
+    let tags = || TestRequest::get().uri("/api/tags").to_request();
+
+    let res = test::call_service(&app, tags()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(header(&res, "ratelimit-limit"), "2");
+    assert_eq!(header(&res, "ratelimit-remaining"), "1");
+    assert_eq!(header(&res, "ratelimit-reset"), "30");
+    assert_eq!(header(&res, "ratelimit-policy"), "2;w=60");
+    test::call_service(&app, tags()).await;
+
+    let res = test::call_service(&app, tags()).await;
+    assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(header(&res, "retry-after"), "30");
+    assert_eq!(header(&res, "ratelimit-remaining"), "0");
+    assert_eq!(header(&res, "ratelimit-reset"), "60");
+}
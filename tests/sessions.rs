@@ -0,0 +1,99 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use serde_json::{json, Value};
+
+fn refresh(refresh_token: &str) -> TestRequest {
+    TestRequest::post()
+        .uri("/api/users/refresh")
+        .set_json(json!({ "user": { "refreshToken": refresh_token } }))
+}
+
+fn current_user(token: &str) -> TestRequest {
+    common::authorized(TestRequest::get().uri("/api/user"), token)
+}
+
+/// The access and refresh tokens of a response that opened a session.
+fn tokens(body: &Value) -> (String, String) {
+    let user = &body["user"];
+    (
+        user["token"].as_str().unwrap().to_string(),
+        user["refreshToken"].as_str().unwrap().to_string(),
+    )
+}
+
+#[actix_web::test]
+async fn refresh_tokens_are_rotated_and_work_once() {
+    let app = test::init_service(common::app()).await;
+    let body: Value =
+        test::call_and_read_body_json(&app, common::register("alice").to_request()).await;
+    let (_, first) = tokens(&body);
+
+    let res = test::call_service(&app, refresh(&first).to_request()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: Value = test::read_body_json(res).await;
+    let (token, second) = tokens(&body);
+    assert_ne!(second, first);
+    let res = test::call_service(&app, current_user(&token).to_request()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    // 用过的刷新令牌不能再换新令牌
+    let res = test::call_service(&app, refresh(&first).to_request()).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    let res = test::call_service(&app, refresh(&second).to_request()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = test::call_service(&app, refresh("made-up").to_request()).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn logout_revokes_only_the_current_session() {
+    let app = test::init_service(common::app()).await;
+    let body: Value =
+        test::call_and_read_body_json(&app, common::register("alice").to_request()).await;
+    let (phone, phone_refresh) = tokens(&body);
+    let body: Value =
+        test::call_and_read_body_json(&app, common::login("alice").to_request()).await;
+    let (laptop, _) = tokens(&body);
+
+    let req = common::authorized(TestRequest::post().uri("/api/user/logout"), &phone);
+    let res = test::call_service(&app, req.to_request()).await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+    let res = test::call_service(&app, current_user(&phone).to_request()).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    let res = test::call_service(&app, refresh(&phone_refresh).to_request()).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    let res = test::call_service(&app, current_user(&laptop).to_request()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn logout_all_revokes_every_session_of_the_user() {
+    let app = test::init_service(common::app()).await;
+    let body: Value =
+        test::call_and_read_body_json(&app, common::register("alice").to_request()).await;
+    let (phone, phone_refresh) = tokens(&body);
+    let body: Value =
+        test::call_and_read_body_json(&app, common::login("alice").to_request()).await;
+    let (laptop, _) = tokens(&body);
+    let body: Value =
+        test::call_and_read_body_json(&app, common::register("bob").to_request()).await;
+    let (bob, _) = tokens(&body);
+
+    let req = common::authorized(TestRequest::post().uri("/api/user/logout-all"), &laptop);
+    let res = test::call_service(&app, req.to_request()).await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+    for token in [&phone, &laptop] {
+        let res = test::call_service(&app, current_user(token).to_request()).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    }
+    let res = test::call_service(&app, refresh(&phone_refresh).to_request()).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    // 别的用户不受影响
+    let res = test::call_service(&app, current_user(&bob).to_request()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
@@ -0,0 +1,165 @@
+mod common;
+
+use actix_web::dev::ServiceResponse;
+use actix_web::http::{header, StatusCode};
+use actix_web::test::{self, TestRequest};
+use serde_json::{json, Value};
+
+fn rename(token: &str, slug: &str, title: &str) -> TestRequest {
+    common::authorized(TestRequest::put(), token)
+        .uri(&format!("/api/articles/{}", slug))
+        .set_json(json!({ "article": { "title": title } }))
+}
+
+fn location(res: &ServiceResponse) -> &str {
+    res.headers()
+        .get(header::LOCATION)
+        .and_then(|location| location.to_str().ok())
+        .unwrap_or_default()
+}
+
+#[actix_web::test]
+async fn repeated_titles_get_numbered_slugs() {
+    let app = test::init_service(common::app()).await;
+    let body: Value =
+        test::call_and_read_body_json(&app, common::register("alice").to_request()).await;
+    let token = body["user"]["token"].as_str().unwrap();
+
+    for expected in ["hello-world", "hello-world-2", "hello-world-3"] {
+        let req = common::create_article(token, "Hello, World!").to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["article"]["slug"], expected);
+    }
+}
+
+#[actix_web::test]
+async fn former_slug_redirects_to_the_current_one() {
+    let app = test::init_service(common::app()).await;
+    let body: Value =
+        test::call_and_read_body_json(&app, common::register("alice").to_request()).await;
+    let token = body["user"]["token"].as_str().unwrap();
+    test::call_service(
+        &app,
+        common::create_article(token, "Hello World").to_request(),
+    )
+    .await;
+
+    let req = rename(token, "hello-world", "Goodbye World").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["article"]["slug"], "goodbye-world");
+
+    // 读请求 301，路径的其余部分和查询串原样保留
+    for (uri, expected) in [
+        ("/api/articles/hello-world", "/api/articles/goodbye-world"),
+        (
+            "/api/articles/hello-world/comments?limit=5",
+            "/api/articles/goodbye-world/comments?limit=5",
+        ),
+    ] {
+        let res = test::call_service(&app, TestRequest::get().uri(uri).to_request()).await;
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY, "GET {}", uri);
+        assert_eq!(location(&res), expected);
+    }
+
+    // 写请求 308，客户端按原方法和请求体重发
+    let req = common::authorized(TestRequest::post(), token)
+        .uri("/api/articles/hello-world/favorite")
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(location(&res), "/api/articles/goodbye-world/favorite");
+
+    let req = TestRequest::get()
+        .uri("/api/articles/no-such-article")
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn former_slugs_are_not_reused() {
+    let app = test::init_service(common::app()).await;
+    let body: Value =
+        test::call_and_read_body_json(&app, common::register("alice").to_request()).await;
+    let token = body["user"]["token"].as_str().unwrap();
+    test::call_service(
+        &app,
+        common::create_article(token, "Hello World").to_request(),
+    )
+    .await;
+    test::call_service(
+        &app,
+        rename(token, "hello-world", "Goodbye World").to_request(),
+    )
+    .await;
+
+    let req = common::create_article(token, "Hello World").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["article"]["slug"], "hello-world-2");
+
+    let req = TestRequest::get()
+        .uri("/api/articles/hello-world")
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(location(&res), "/api/articles/goodbye-world");
+
+    // 改回原标题时取回自己用过的 slug
+    let req = rename(token, "goodbye-world", "Hello World").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["article"]["slug"], "hello-world");
+}
+
+#[actix_web::test]
+async fn titles_matching_fixed_routes_stay_readable() {
+    let app = test::init_service(common::app()).await;
+    let body: Value =
+        test::call_and_read_body_json(&app, common::register("alice").to_request()).await;
+    let token = body["user"]["token"].as_str().unwrap();
+
+    // /api/articles/search 和 /api/articles/feed 先于 {slug} 匹配
+    for (title, expected) in [("Search", "search-2"), ("Feed", "feed-2")] {
+        let req = common::create_article(token, title).to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["article"]["slug"], expected);
+
+        let req = TestRequest::get()
+            .uri(&format!("/api/articles/{}", expected))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: Value = test::read_body_json(res).await;
+        assert_eq!(body["article"]["title"], title);
+    }
+}
+
+#[actix_web::test]
+async fn numbers_from_the_title_are_not_collision_suffixes() {
+    let app = test::init_service(common::app()).await;
+    let body: Value =
+        test::call_and_read_body_json(&app, common::register("alice").to_request()).await;
+    let token = body["user"]["token"].as_str().unwrap();
+    let req = common::create_article(token, "Hello World 2024").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["article"]["slug"], "hello-world-2024");
+
+    let req = rename(token, "hello-world-2024", "Hello World").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["article"]["slug"], "hello-world");
+
+    let req = TestRequest::get()
+        .uri("/api/articles/hello-world-2024")
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(location(&res), "/api/articles/hello-world");
+
+    // 真正的冲突后缀在标题基本不变时保留
+    test::call_service(
+        &app,
+        common::create_article(token, "Hello World").to_request(),
+    )
+    .await;
+    let req = rename(token, "hello-world-2", "Hello, World!").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["article"]["slug"], "hello-world-2");
+}
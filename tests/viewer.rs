@@ -0,0 +1,72 @@
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use serde_json::Value;
+
+/// A GET as `token`, or anonymously.
+fn get(uri: &str, token: Option<&str>) -> TestRequest {
+    let req = TestRequest::get().uri(uri);
+    match token {
+        Some(token) => common::authorized(req, token),
+        None => req,
+    }
+}
+
+#[actix_web::test]
+async fn public_endpoints_are_personalised_for_the_viewer() {
+    let app = test::init_service(common::app()).await;
+    let mut tokens = Vec::new();
+    for username in ["alice", "bob", "carol"] {
+        let body: Value =
+            test::call_and_read_body_json(&app, common::register(username).to_request()).await;
+        tokens.push(body["user"]["token"].as_str().unwrap().to_string());
+    }
+    let (alice, bob, carol) = (&tokens[0], &tokens[1], &tokens[2]);
+    test::call_service(&app, common::create_article(alice, "Dragons").to_request()).await;
+    test::call_service(
+        &app,
+        common::comment(alice, "dragons", "First").to_request(),
+    )
+    .await;
+    test::call_service(&app, common::favorite(bob, "dragons").to_request()).await;
+    let req = common::authorized(TestRequest::post(), bob).uri("/api/profiles/alice/follow");
+    test::call_service(&app, req.to_request()).await;
+
+    // bob 收藏并关注了，carol 和匿名访问者都没有
+    for (viewer, expected) in [(Some(bob), true), (Some(carol), false), (None, false)] {
+        let viewer = viewer.map(String::as_str);
+        let req = get("/api/articles/dragons", viewer).to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["article"]["favorited"], expected, "{:?}", viewer);
+        assert_eq!(body["article"]["favoritesCount"], 1);
+        assert_eq!(body["article"]["author"]["following"], expected);
+
+        let req = get("/api/articles", viewer).to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["articles"][0]["favorited"], expected, "{:?}", viewer);
+        assert_eq!(body["articles"][0]["author"]["following"], expected);
+
+        let req = get("/api/articles/dragons/comments", viewer).to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["comments"][0]["author"]["following"], expected);
+
+        let req = get("/api/profiles/alice", viewer).to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["profile"]["following"], expected, "{:?}", viewer);
+    }
+
+    // 作者本人不算收藏，也不算关注自己
+    let req = get("/api/articles/dragons", Some(alice)).to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["article"]["favorited"], false);
+    assert_eq!(body["article"]["author"]["following"], false);
+}
+
+#[actix_web::test]
+async fn an_invalid_token_is_rejected_rather_than_ignored() {
+    let app = test::init_service(common::app()).await;
+    let req = get("/api/articles", Some("not-a-token")).to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+}